@@ -0,0 +1,23 @@
+// the tiny runtime every yeller program gets linked against
+
+#include <stdio.h>
+#include <stdlib.h>
+
+void *yeller_alloc(unsigned long size) {
+    void *ptr = calloc(1, size);
+    if (ptr == NULL) {
+        fputs("yeller: there is no room left for you\n", stderr);
+        abort();
+    }
+    return ptr;
+}
+
+void yeller_free(void *ptr) {
+    free(ptr);
+}
+
+// where a failed "i insist" ends up
+void yeller_trap(const char *message) {
+    fputs(message, stderr);
+    abort();
+}
@@ -1,30 +1,46 @@
 #![allow(dead_code)]
 
 use std::io::Write;
+use crate::parser::nodes;
+use crate::warnings;
 
 #[derive(Debug, Clone)]
 pub struct Error {
     pub kind: ErrorKind,
     pub line: usize,
+    // set when the error is in an imported file instead of the one being compiled
+    pub file: Option<String>,
 }
 
 impl Error {
     pub fn new(kind: ErrorKind, line: usize) -> Self {
-        Self { kind, line }
+        Self { kind, line, file: None }
+    }
+
+    pub fn in_file(mut self, file: Option<String>) -> Self {
+        if self.file.is_none() {
+            self.file = file;
+        }
+        self
     }
 
     pub fn report(&self, input: &str) {
-        let final_line = if self.line < input.lines().count() {
-            let line = input.lines().nth(self.line - 1).unwrap();
-            let line_number = format!("{} | ", self.line);
-            format!("{}{}", line_number, line)
-        } else {
-            format!("{} | LALALALALA I CAN'T HEAR YOU", self.line)
-        };
+        let final_line = source_line(self.line, input);
         return self.kind.report(&final_line);
     }
 }
 
+// the line the problem is on, with its number in front
+pub fn source_line(line: usize, input: &str) -> String {
+    if line < input.lines().count() {
+        let text = input.lines().nth(line - 1).unwrap();
+        let line_number = format!("{} | ", line);
+        format!("{}{}", line_number, text)
+    } else {
+        format!("{} | LALALALALA I CAN'T HEAR YOU", line)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum ErrorKind {
     UnexpectedToken {
@@ -40,14 +56,75 @@ pub enum ErrorKind {
     VariableAlreadyDeclared(String),
     PackedFunc(u32),
     UnexpectedChar(char),
-    VariableNotDeclared(String),
+    // the name, and a name that's in sight which it was probably meant to be
+    VariableNotDeclared(String, Option<String>),
     RandomChance,
     Tabbing,
     ExtraLine,
-    ShortVarName(String),
+    // the name and how long names have to be
+    ShortVarName(String, usize),
     LongFuncName(String),
     InvalidAssignmentTarget,
-    TypeError,
+    // a type that isn't built in and isn't one of the function's type parameters
+    UnknownType(String),
+    MismatchedTypes { expected: nodes::Type, found: nodes::Type },
+    // a function or nothing somewhere only a real value can go
+    NotStorable(nodes::Type),
+    // a value given back by a function that returns nothing
+    ReturnedFromNothing,
+    // a bare return in a function that's meant to give back this
+    EmptyReturn(nodes::Type),
+    // a null with nothing around it to say what it points at
+    UntypedNull,
+    BoxInBox,
+    // handing over a box that belongs to something else
+    BoxNotOwned,
+    GenericWithoutBody(String),
+    GenericAsValue(String),
+    UninferredTypeParam { function: String, param: String },
+    // a generic function that keeps calling itself with bigger and bigger types
+    TooManyInstances(String),
+    // variadic is set when more than expected would have been fine too
+    WrongArgCount { function: String, expected: usize, found: usize, variadic: bool },
+    // the types are boxed to keep every error small
+    WrongArgType { function: String, position: usize, expected: Box<nodes::Type>, found: Box<nodes::Type> },
+    NotCallable(nodes::Type),
+    BadDereference(nodes::Type),
+    BadSubscript(nodes::Type),
+    BadAddressOf,
+    MissingReturn(String),
+    MaybeUnassigned(String),
+    // a warning that was turned into an error with --deny
+    DeniedWarning(warnings::WarningKind),
+    TooDeep { depth: u32, max: u32 },
+    // two parameters next to each other that are the wrong way around
+    UnsortedParameters { first: String, second: String },
+    Impolite { polite: u32, items: u32 },
+    // something in yeller.toml that can't be made sense of
+    BadConfig(String),
+    DuplicateCase(u64),
+    BadCaseRange(u64, u64),
+    VariadicDefinition(String),
+    // the position of an extra argument c's varargs can't carry
+    BadVariadicArg(usize),
+    UseAfterMove(String),
+    ModuleNotFound(String),
+    ModuleNotImported(String),
+    // a C function declared differently in two files
+    ExternMismatch(String),
+    NotConstant,
+    ConstOverflow,
+    DivideByZero,
+    StaticAssertFailed,
+    ReturnInDefer,
+    UsedNothing,
+    BadPointerMath,
+    // what llvm said when it checked the module, which means the compiler got something wrong
+    InvalidModule(String),
+    // what clang said when it couldn't link the program
+    LinkFailed(String),
+    // never returned by the compiler, its text is what a failed "i insist" prints when the program runs
+    AssertionFailed,
 }
 
 impl ErrorKind {
@@ -58,7 +135,25 @@ impl ErrorKind {
     }
 
     pub fn to_speech(&self) -> String {
-        let text = match self {
+        let text = self.text();
+
+        // call "python3 speech.py" with the error message
+
+        std::process::Command::new("python3")
+            .arg("speech.py")
+            .stdin(std::process::Stdio::piped())
+            .spawn()
+            .expect("Failed to run speech.py")
+            .stdin
+            .unwrap()
+            .write_all(text.as_bytes())
+            .expect("Failed to write to stdin of speech.py");
+
+        text
+    }
+
+    pub fn text(&self) -> String {
+        match self {
             Self::UnexpectedToken { expected, found } => {
                 format!("You dumbass, you wrote {}, when I wanted {}", found, expected)
             }
@@ -85,9 +180,12 @@ impl ErrorKind {
             Self::VariableAlreadyDeclared(name) => {
                 format!("Hey man, I heard you like variables, but wow! You can't have two whole variables in your program named {}. Someone might get confused!", name)
             }
-            Self::VariableNotDeclared(name) => {
+            Self::VariableNotDeclared(name, None) => {
                 format!("Buddy... this is meant to be a bad compiler... how are you making these kinds of mistakes... {} doesn't exist buddy...", name)
             }
+            Self::VariableNotDeclared(name, Some(suggestion)) => {
+                format!("Buddy... {} doesn't exist buddy... but {} does. It's right there. You typed it yourself. Learn to spell.", name, suggestion)
+            }
             Self::RandomChance => {
                 "I, as the compiler, have decided that I hate you and your code. I shall now fail. Goodbye.".to_string()
             }
@@ -97,8 +195,8 @@ impl ErrorKind {
             Self::Tabbing => {
                 "Why do you have spacing before your line? Stop wasting peoples time by making them have to move their eyes to the start of the actually useful stuff!".to_string()
             }
-            Self::ShortVarName(name) => {
-                format!("My sir, thou shalt not name a vaariii-able with less than 7 characters! It is quite simply vulgur and unpleasant to the eyes! Please, m'lord, pick a better name for thy variable than {}!", name)
+            Self::ShortVarName(name, min_length) => {
+                format!("My sir, thou shalt not name a vaariii-able with less than {} characters! It is quite simply vulgur and unpleasant to the eyes! Please, m'lord, pick a better name for thy variable than {}!", min_length, name)
             }
             Self::LongFuncName(name) => {
                 format!("\"{}\" br u nt skspr 💔", name)
@@ -106,35 +204,151 @@ impl ErrorKind {
             Self::InvalidAssignmentTarget => {
                 "Bro WHAT are you trying to assign to 💔".to_string()
             }
-            Self::TypeError => {
-                "Dude how did you manage to get a fucking type error in this bullshit language".to_string()
+            Self::UnknownType(name) => {
+                format!("What on earth is a {}? That's not a type. If you meant it as a type parameter, you have to say so with \"for any type named\".", name)
             }
-        };
-
-        // call "python3 speech.py" with the error message
-
-        std::process::Command::new("python3")
-            .arg("speech.py")
-            .stdin(std::process::Stdio::piped())
-            .spawn()
-            .expect("Failed to run speech.py")
-            .stdin
-            .unwrap()
-            .write_all(text.as_bytes())
-            .expect("Failed to write to stdin of speech.py");
-
-        text
+            Self::MismatchedTypes { expected, found } => {
+                format!("I wanted {} and you gave me {}. Dude how did you manage to get a type error in this language", expected.spelling(), found.spelling())
+            }
+            Self::NotStorable(ty) => {
+                format!("You can't keep {} anywhere. Functions only go in variables as pointers, and nothing is, shockingly, nothing.", ty.spelling())
+            }
+            Self::ReturnedFromNothing => {
+                "This function gives back nothing. You said so yourself. So why are you trying to hand me something?".to_string()
+            }
+            Self::EmptyReturn(ty) => {
+                format!("You promised {} and then returned with empty hands. Liar.", ty.spelling())
+            }
+            Self::UntypedNull => {
+                "A null pointing at what? I can't read your mind. Put it somewhere that says what it points at.".to_string()
+            }
+            Self::BoxInBox => {
+                "A box in a box? Who's going to unpack the inner one, you? Nobody. That's who.".to_string()
+            }
+            Self::BoxNotOwned => {
+                "That box isn't yours to give away. Only a brand new box or one you named yourself can change hands.".to_string()
+            }
+            Self::GenericWithoutBody(name) => {
+                format!("{} works for any type, but there's nothing in it to copy for each one. Give it a body or give up the types.", name)
+            }
+            Self::GenericAsValue(name) => {
+                format!("{} doesn't exist until you call it with something, so it has no address yet. Call it first.", name)
+            }
+            Self::UninferredTypeParam { function, param } => {
+                format!("How am I meant to know what {} is when calling {}? None of the parameters use it. I'm a compiler, not a psychic.", param, function)
+            }
+            Self::TooManyInstances(name) => {
+                format!("I've made so many copies of {} that I've lost count. It keeps calling itself with bigger types. I'm stopping here.", name)
+            }
+            Self::WrongArgCount { function, expected, found, variadic } => {
+                let at_least = if *variadic { "at least " } else { "" };
+                format!("{} takes {}{} parameters and you gave it {}. Counting is a skill, you should pick it up sometime.", function, at_least, expected, found)
+            }
+            Self::WrongArgType { function, position, expected, found } => {
+                format!("Parameter number {} of {} is supposed to be {}, and you handed it {}. Not even close.", position, function, expected.spelling(), found.spelling())
+            }
+            Self::NotCallable(found) => {
+                format!("You can't invoke {}. It's not a function, no matter how nicely you ask.", found.spelling())
+            }
+            Self::BadDereference(found) => {
+                format!("You asked what {} is pointing at. It's not pointing at anything, because it isn't a pointer.", found.spelling())
+            }
+            Self::BadSubscript(found) => {
+                format!("You can only index into something pointing at things, and {} is not that.", found.spelling())
+            }
+            Self::MissingReturn(name) => {
+                format!("{} is supposed to give something back, but it can get all the way to the end without returning. What do you expect me to hand over, a hug?", name)
+            }
+            Self::MaybeUnassigned(name) => {
+                format!("You're reading {} before anything was ever put in it. There's nothing in there, just like your head.", name)
+            }
+            Self::DeniedWarning(kind) => {
+                format!("{} You said you wouldn't put up with that, so neither will I.", kind.text())
+            }
+            Self::TooDeep { depth, max } => {
+                format!("{} blocks deep?! I stopped following at {}. Nobody can read this, flatten it out!", depth, max)
+            }
+            Self::UnsortedParameters { first, second } => {
+                format!("{} comes before {}, you absolute walnut. A, B, C. Parameters go in alphabetical order, like a civilised person would do.", second, first)
+            }
+            Self::Impolite { polite, items } => {
+                format!("Out of the {} things you told me to do, you said please for {}. Were you raised in a barn? Ask nicely.", items, polite)
+            }
+            Self::BadConfig(message) => {
+                format!("I read your config so you didn't have to. Good thing too. {}", message)
+            }
+            Self::BadAddressOf => {
+                "That doesn't live anywhere, so it doesn't have an address. Put it in a variable first like a normal person.".to_string()
+            }
+            Self::DuplicateCase(value) => {
+                format!("You already told me what to do when it is {}. Make up your mind!", value)
+            }
+            Self::BadCaseRange(low, high) => {
+                format!("{} through {}? Have you ever even seen a number line? Keep your ranges forwards and reasonably sized!", low, high)
+            }
+            Self::UseAfterMove(name) => {
+                format!("You already gave {} away! No take-backsies! Get your own box!", name)
+            }
+            Self::ModuleNotFound(name) => {
+                format!("I looked everywhere for {}.yl. Under the couch, behind the fridge, EVERYWHERE. It isn't there. Stop making things up.", name)
+            }
+            Self::ModuleNotImported(name) => {
+                format!("You can't just take things from {} without importing it first. Did nobody teach you to ask?", name)
+            }
+            Self::ExternMismatch(name) => {
+                format!("One file says {} looks like this and another says it looks like that. They can't both be right, and I'm not picking for you.", name)
+            }
+            Self::NotConstant => {
+                "I need to know this one before your program even runs, and you gave me something that changes. What am I, a fortune teller?".to_string()
+            }
+            Self::ConstOverflow => {
+                "That number is way too big for 32 bits. I did the maths so you don't have to, and you should be ashamed.".to_string()
+            }
+            Self::DivideByZero => {
+                "Dividing by zero?! In MY compiler?! I'm not even going to let this one run.".to_string()
+            }
+            Self::StaticAssertFailed => {
+                "You said you knew that for a fact. You were wrong. It's zero. Embarrassing.".to_string()
+            }
+            Self::ReturnInDefer => {
+                "You're returning while the function is already on its way out? Make up your mind.".to_string()
+            }
+            Self::UsedNothing => {
+                "That function gives you NOTHING back. You can't do anything with nothing. Think about what you've done.".to_string()
+            }
+            Self::BadPointerMath => {
+                "You can move a pointer along, and you can see how far apart two of them are. That's it. Whatever you just tried to do to that pointer, stop.".to_string()
+            }
+            Self::InvalidModule(message) => {
+                format!("Okay, this one's on me. I made something llvm won't take: {}", message)
+            }
+            Self::LinkFailed(message) => {
+                format!("clang couldn't stick your program together. Here's what it had to say: {}", message)
+            }
+            Self::AssertionFailed => {
+                "You insisted. You INSISTED. And it was zero anyway. I'm shutting this whole thing down.".to_string()
+            }
+            Self::VariadicDefinition(name) => {
+                format!("And then some more WHAT? {} wants a body AND infinite parameters? Only C gets to do that. Leave the body off.", name)
+            }
+            Self::BadVariadicArg(position) => {
+                format!("Parameter number {} is one of the \"some more\" ones, and those only get whole numbers and pointers to things. C wouldn't know what to do with anything else and neither do I.", position)
+            }
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use super::super::compile;
+    use super::super::{compile, Options};
 
     fn test_error(file: &str, expected_error: ErrorKind) {
-        let input = std::fs::read_to_string(file).expect("Failed to read input file");
-        match compile(&input, "____doesnt______mattttter____") {
+        test_error_with(file, &Options::default(), expected_error);
+    }
+
+    fn test_error_with(file: &str, options: &Options, expected_error: ErrorKind) {
+        match compile(file, "____doesnt______mattttter____", options) {
             Ok(_) => panic!("Compilation should have failed!"),
             Err(e) => {
                 if let ErrorKind::RandomChance = e.kind {
@@ -167,7 +381,7 @@ mod tests {
 
     #[test]
     fn test_short_var_name() {
-        test_error("error_examples/short_var_name.yl", ErrorKind::ShortVarName("var".to_string()));
+        test_error("error_examples/short_var_name.yl", ErrorKind::ShortVarName("var".to_string(), 7));
     }
 
     #[test]
@@ -175,6 +389,12 @@ mod tests {
         test_error("error_examples/tabbing.yl", ErrorKind::Tabbing);
     }
 
+    #[test]
+    fn test_tabbing_after_note() {
+        // only lines that are nothing but note get to be laid out however they like
+        test_error("error_examples/tabbing_after_note.yl", ErrorKind::Tabbing);
+    }
+
     #[test]
     fn test_unexpected_char() {
         test_error("error_examples/unexpected_char.yl", ErrorKind::UnexpectedChar('@'));
@@ -185,6 +405,11 @@ mod tests {
         test_error("error_examples/unexpected_eof.yl", ErrorKind::UnexpectedEOF);
     }
 
+    #[test]
+    fn test_unterminated_note() {
+        test_error("error_examples/unterminated_note.yl", ErrorKind::UnexpectedEOF);
+    }
+
     #[test]
     fn test_unexpected_token() {
         test_error("error_examples/unexpected_token.yl", ErrorKind::UnexpectedToken {
@@ -195,12 +420,133 @@ mod tests {
 
     #[test]
     fn test_variable_already_declared() {
-        test_error("error_examples/var_already_declared.yl", ErrorKind::VariableAlreadyDeclared("varrrriable".to_string()));
+        test_error("error_examples/var_redeclared.yl", ErrorKind::VariableAlreadyDeclared("varrrriable".to_string()));
+    }
+
+    #[test]
+    fn test_no_shadowing() {
+        let options = Options { no_shadowing: true, ..Options::default() };
+        test_error_with("error_examples/var_already_declared.yl", &options, ErrorKind::VariableAlreadyDeclared("varrrriable".to_string()));
+    }
+
+    #[test]
+    fn test_denied_warning() {
+        let mut options = Options::default();
+        options.lints.set("unused-variable", warnings::Severity::Deny);
+        test_error_with("error_examples/denied_warning.yl", &options, ErrorKind::DeniedWarning(warnings::WarningKind::UnusedVariable("forgotten".to_string())));
+    }
+
+    #[test]
+    fn test_unreachable_code() {
+        let mut options = Options::default();
+        options.lints.set("unreachable-code", warnings::Severity::Deny);
+        test_error_with("error_examples/unreachable_code.yl", &options, ErrorKind::DeniedWarning(warnings::WarningKind::UnreachableCode));
+    }
+
+    #[test]
+    fn test_written_only() {
+        // putting things in a variable nobody ever reads doesn't count as using it
+        let mut options = Options::default();
+        options.lints.set("unused-variable", warnings::Severity::Deny);
+        test_error_with("error_examples/written_only.yl", &options, ErrorKind::DeniedWarning(warnings::WarningKind::UnusedVariable("scribble".to_string())));
+    }
+
+    #[test]
+    fn test_unused_parameter() {
+        let mut options = Options::default();
+        options.lints.set("unused-parameter", warnings::Severity::Deny);
+        test_error_with("error_examples/unused_parameter.yl", &options, ErrorKind::DeniedWarning(warnings::WarningKind::UnusedParameter("ignored".to_string())));
+    }
+
+    #[test]
+    fn test_unused_function() {
+        // the only call to spin is from spin itself
+        let mut options = Options::default();
+        options.lints.set("unused-function", warnings::Severity::Deny);
+        test_error_with("error_examples/unused_function.yl", &options, ErrorKind::DeniedWarning(warnings::WarningKind::UnusedFunction("spin".to_string())));
+    }
+
+    #[test]
+    fn test_dead_assignment() {
+        let mut options = Options::default();
+        options.lints.set("dead-assignment", warnings::Severity::Deny);
+        test_error_with("error_examples/dead_assignment.yl", &options, ErrorKind::DeniedWarning(warnings::WarningKind::DeadAssignment("counter".to_string())));
+    }
+
+    #[test]
+    fn test_endless_recursion() {
+        let mut options = Options::default();
+        options.lints.set("endless-recursion", warnings::Severity::Deny);
+        test_error_with("error_examples/endless_recursion.yl", &options, ErrorKind::DeniedWarning(warnings::WarningKind::EndlessRecursion(vec!["spin".to_string()])));
+    }
+
+    #[test]
+    fn test_tuned_rule() {
+        let mut options = Options::default();
+        options.rules.configure("[rules.short-variable-name]\nmin-length = 12").unwrap();
+        test_error_with("error_examples/var_already_declared.yl", &options, ErrorKind::ShortVarName("varrrriable".to_string(), 12));
+    }
+
+    #[test]
+    fn test_rule_set_to_warn() {
+        let mut options = Options::default();
+        options.rules.configure("[rules]\nlonely-variable = \"warn\"").unwrap();
+        options.lints.set("house-rule", warnings::Severity::Deny);
+        test_error_with("error_examples/lonely_variable.yl", &options, ErrorKind::DeniedWarning(warnings::WarningKind::BrokenRule(Box::new(ErrorKind::LonelyVariable))));
+    }
+
+    fn test_optional_rule(file: &str, rule: &str, expected_error: ErrorKind) {
+        let mut options = Options::default();
+        options.rules.configure(&format!("[rules]\n{} = \"error\"", rule)).unwrap();
+        test_error_with(file, &options, expected_error);
+    }
+
+    #[test]
+    fn test_too_deep() {
+        test_optional_rule("error_examples/too_deep.yl", "nesting-depth", ErrorKind::TooDeep { depth: 5, max: 4 });
+    }
+
+    #[test]
+    fn test_unsorted_params() {
+        test_optional_rule("error_examples/unsorted_params.yl", "alphabetical-parameters", ErrorKind::UnsortedParameters {
+            first: "second_one".to_string(),
+            second: "first_one".to_string(),
+        });
+    }
+
+    #[test]
+    fn test_impolite() {
+        test_optional_rule("error_examples/impolite.yl", "politeness", ErrorKind::Impolite { polite: 0, items: 2 });
+    }
+
+    #[test]
+    fn test_bad_config() {
+        let mut options = Options::default();
+        let error = options.rules.configure("[rules]\nshort-variable-name = \"sometimes\"").unwrap_err();
+        assert_eq!(error.line, 2);
+        assert!(matches!(error.kind, ErrorKind::BadConfig(_)));
     }
 
     #[test]
     fn test_variable_not_declared() {
-        test_error("error_examples/var_not_declared.yl", ErrorKind::VariableNotDeclared("varrrriable".to_string()));
+        test_error("error_examples/var_not_declared.yl", ErrorKind::VariableNotDeclared("varrrriable".to_string(), None));
+    }
+
+    #[test]
+    fn test_variable_misspelled() {
+        test_error("error_examples/var_misspelled.yl", ErrorKind::VariableNotDeclared("varriable".to_string(), Some("varrrriable".to_string())));
+    }
+
+    #[test]
+    fn test_do_while_scope() {
+        // the condition is outside the body, so it can't see what the body declared
+        test_error("error_examples/do_while_scope.yl", ErrorKind::VariableNotDeclared("leftover".to_string(), None));
+    }
+
+    #[test]
+    fn test_variadic_too_few() {
+        // the extras are optional but the named ones aren't
+        test_error("error_examples/variadic_too_few.yl", ErrorKind::WrongArgCount { function: "printf".to_string(), expected: 1, found: 0, variadic: true });
     }
 
     #[test]
@@ -213,6 +559,190 @@ mod tests {
 
     #[test]
     fn test_invalid_assignment_target() {
-        test_error("error_examples/invalid_assign_target.yl", ErrorKind::InvalidAssignmentTarget);
+        // main only has argc, which would be lonely before it ever got to the assignment
+        let mut options = Options::default();
+        options.rules.configure("[rules]\nlonely-variable = \"off\"").unwrap();
+        test_error_with("error_examples/invalid_assign_target.yl", &options, ErrorKind::InvalidAssignmentTarget);
+    }
+
+    #[test]
+    fn test_invalid_assignment_in_else_if() {
+        test_error("error_examples/invalid_assign_in_else_if.yl", ErrorKind::InvalidAssignmentTarget);
+    }
+
+    #[test]
+    fn test_duplicate_case() {
+        test_error("error_examples/duplicate_case.yl", ErrorKind::DuplicateCase(3));
+    }
+
+    #[test]
+    fn test_bad_case_range() {
+        test_error("error_examples/bad_case_range.yl", ErrorKind::BadCaseRange(5, 2));
+    }
+
+    #[test]
+    fn test_function_variable() {
+        test_error("error_examples/function_variable.yl", ErrorKind::NotStorable(nodes::Type::Function(Vec::new(), Box::new(nodes::Type::I32), false)));
+    }
+
+    #[test]
+    fn test_variadic_definition() {
+        test_error("error_examples/variadic_definition.yl", ErrorKind::VariadicDefinition("vari".to_string()));
+    }
+
+    #[test]
+    fn test_bad_variadic_arg() {
+        test_error("error_examples/bad_variadic_arg.yl", ErrorKind::BadVariadicArg(3));
+    }
+
+    #[test]
+    fn test_use_after_move() {
+        test_error("error_examples/use_after_move.yl", ErrorKind::UseAfterMove("varrrriable".to_string()));
+    }
+
+    #[test]
+    fn test_module_not_found() {
+        test_error("error_examples/module_not_found.yl", ErrorKind::ModuleNotFound("nowhere".to_string()));
+    }
+
+    #[test]
+    fn test_module_not_imported() {
+        test_error("error_examples/module_not_imported.yl", ErrorKind::ModuleNotImported("mathh".to_string()));
+    }
+
+    #[test]
+    fn test_extern_mismatch() {
+        test_error("module_examples/extern_mismatch.yl", ErrorKind::ExternMismatch("printf".to_string()));
+    }
+
+    #[test]
+    fn test_unknown_type() {
+        test_error("error_examples/unknown_type.yl", ErrorKind::UnknownType("whatever".to_string()));
+    }
+
+    #[test]
+    fn test_unknown_return_type() {
+        test_error("error_examples/unknown_return_type.yl", ErrorKind::UnknownType("other".to_string()));
+    }
+
+    #[test]
+    fn test_tuple_mismatch() {
+        test_error("error_examples/tuple_mismatch.yl", ErrorKind::MismatchedTypes {
+            expected: nodes::Type::Tuple(vec![nodes::Type::I32, nodes::Type::I32, nodes::Type::I32]),
+            found: nodes::Type::Tuple(vec![nodes::Type::I32, nodes::Type::I32]),
+        });
+    }
+
+    #[test]
+    fn test_return_mismatch() {
+        test_error("error_examples/return_mismatch.yl", ErrorKind::MismatchedTypes {
+            expected: nodes::Type::I32,
+            found: nodes::Type::Pointer(Box::new(nodes::Type::I32)),
+        });
+    }
+
+    #[test]
+    fn test_declaration_mismatch() {
+        test_error("error_examples/declaration_mismatch.yl", ErrorKind::MismatchedTypes {
+            expected: nodes::Type::I32,
+            found: nodes::Type::Pointer(Box::new(nodes::Type::I32)),
+        });
+    }
+
+    #[test]
+    fn test_empty_return() {
+        test_error("error_examples/empty_return.yl", ErrorKind::EmptyReturn(nodes::Type::I32));
+    }
+
+    fn test_wrong_arg_count() {
+        test_error("error_examples/wrong_arg_count.yl", ErrorKind::WrongArgCount {
+            function: "dubl".to_string(),
+            expected: 1,
+            found: 2,
+            variadic: false,
+        });
+    }
+
+    #[test]
+    fn test_wrong_arg_type() {
+        test_error("error_examples/wrong_arg_type.yl", ErrorKind::WrongArgType {
+            function: "dubl".to_string(),
+            position: 1,
+            expected: Box::new(nodes::Type::I32),
+            found: Box::new(nodes::Type::Pointer(Box::new(nodes::Type::I32))),
+        });
+    }
+
+    #[test]
+    fn test_not_callable() {
+        test_error("error_examples/not_callable.yl", ErrorKind::NotCallable(nodes::Type::I32));
+    }
+
+    #[test]
+    fn test_bad_dereference() {
+        test_error("error_examples/bad_dereference.yl", ErrorKind::BadDereference(nodes::Type::I32));
+    }
+
+    #[test]
+    fn test_bad_subscript() {
+        test_error("error_examples/bad_subscript.yl", ErrorKind::BadSubscript(nodes::Type::I32));
+    }
+
+    #[test]
+    fn test_bad_address_of() {
+        test_error("error_examples/bad_address_of.yl", ErrorKind::BadAddressOf);
+    }
+
+    #[test]
+    fn test_tuple_return_shape() {
+        test_error("error_examples/tuple_return_shape.yl", ErrorKind::MismatchedTypes {
+            expected: nodes::Type::Tuple(vec![nodes::Type::I32, nodes::Type::I32]),
+            found: nodes::Type::Tuple(vec![nodes::Type::I32, nodes::Type::I32, nodes::Type::I32]),
+        });
+    }
+
+    #[test]
+    fn test_missing_return() {
+        test_error("error_examples/missing_return.yl", ErrorKind::MissingReturn("pick".to_string()));
+    }
+
+    #[test]
+    fn test_generic_uninferred() {
+        test_error("error_examples/generic_uninferred.yl", ErrorKind::UninferredTypeParam { function: "make".to_string(), param: "thing".to_string() });
+    }
+
+    #[test]
+    fn test_const_divide_by_zero() {
+        test_error("error_examples/const_divide_by_zero.yl", ErrorKind::DivideByZero);
+    }
+
+    #[test]
+    fn test_const_overflow() {
+        test_error("error_examples/const_overflow.yl", ErrorKind::ConstOverflow);
+    }
+
+    #[test]
+    fn test_static_assert() {
+        test_error("error_examples/static_assert.yl", ErrorKind::StaticAssertFailed);
+    }
+
+    #[test]
+    fn test_not_constant() {
+        test_error("error_examples/not_constant.yl", ErrorKind::NotConstant);
+    }
+
+    #[test]
+    fn test_return_in_defer() {
+        test_error("error_examples/return_in_defer.yl", ErrorKind::ReturnInDefer);
+    }
+
+    #[test]
+    fn test_used_nothing() {
+        test_error("error_examples/used_nothing.yl", ErrorKind::UsedNothing);
+    }
+
+    #[test]
+    fn test_bad_pointer_math() {
+        test_error("error_examples/bad_pointer_math.yl", ErrorKind::BadPointerMath);
     }
 }
\ No newline at end of file
@@ -1,21 +1,44 @@
 use crate::errors;
+use crate::lexer;
+use crate::rules;
+use crate::warnings;
 
-pub fn formatting_check(input: &str) -> Result<(), errors::Error> { // true if successful, false if failed
-    if !check_for_tabs(input) {
-        return Err(errors::Error::new(errors::ErrorKind::Tabbing, usize::MAX));
-    }
+// the rules about how a file is laid out live in rules.rs, this works out what they get to look at
+pub fn formatting_check(input: &str, rules: &rules::RuleSet, warnings: &mut Vec<warnings::Warning>) -> Result<(), errors::Error> {
+    let in_note = lines_in_notes(input);
 
-    if !check_for_empties(input) {
-        return Err(errors::Error::new(errors::ErrorKind::ExtraLine, usize::MAX));
-    }
-
-    Ok(())
+    rules.check(&rules::Subject::Source { input, in_note: &in_note }, usize::MAX, warnings)
 }
 
-fn check_for_tabs(input: &str) -> bool {
-    !input.lines().any(|line| if let Some(c) = line.chars().nth(0) { c.is_whitespace() } else { false })
-}
+// lines that carry on a note from an earlier line are prose, so they can be laid out however
+// they like. once code follows the end of the note the line is held to the usual rules again
+fn lines_in_notes(input: &str) -> Vec<bool> {
+    let mut in_note = false;
+
+    input.lines().map(|line| {
+        let started_in_note = in_note;
+        let mut code = false;
 
-fn check_for_empties(input: &str) -> bool {
-    !input.lines().any(|line| line.is_empty())
-}
\ No newline at end of file
+        let mut position = 0;
+        while let Some(c) = line[position..].chars().next() {
+            if in_note {
+                if lexer::phrase_at(line, position, lexer::NOTE_END) {
+                    in_note = false;
+                    position += lexer::NOTE_END.len();
+                    continue;
+                }
+            } else if lexer::phrase_at(line, position, lexer::NOTE_START) {
+                in_note = true;
+                position += lexer::NOTE_START.len();
+                continue;
+            } else if lexer::phrase_at(line, position, lexer::SIDE_NOTE) {
+                break;
+            } else if !c.is_whitespace() {
+                code = true;
+            }
+            position += c.len_utf8();
+        }
+
+        started_in_note && !code
+    }).collect()
+}
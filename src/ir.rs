@@ -1,4 +1,7 @@
 pub mod definition;
+pub mod flow;
+pub mod definite_assignment;
+pub mod liveness;
 
 use crate::parser::nodes;
 use crate::errors;
@@ -7,11 +10,21 @@ use crate::semantic_analysis::typecheck::{STEntry, SymbolTable};
 pub struct IRGenerator {
     tmp_counter: u64,
     pub symbol_table: SymbolTable,
+    // what has to happen when each scope we're currently inside is left, last in first out
+    owned_scopes: Vec<Vec<Cleanup>>,
+    // assertions are left out of release builds
+    release: bool,
+}
+
+#[derive(Clone)]
+enum Cleanup {
+    Free(String),
+    Deferred(nodes::Statement),
 }
 
 impl IRGenerator {
-    pub fn new(symbol_table: SymbolTable) -> Self {
-        Self { tmp_counter: 0, symbol_table }
+    pub fn new(symbol_table: SymbolTable, release: bool) -> Self {
+        Self { tmp_counter: 0, symbol_table, owned_scopes: Vec::new(), release }
     }
 
     pub fn generate_ir(&mut self, program: nodes::Program) -> Result<definition::Program, errors::Error> {
@@ -36,17 +49,23 @@ impl IRGenerator {
             return Ok(None);
         };
 
+        // the function owns any boxes it was handed
+        self.owned_scopes.push(function.params.iter().filter(|(_, ty)| matches!(ty, nodes::Type::Box(_))).map(|(name, _)| Cleanup::Free(name.clone())).collect());
         self.generate_block(block, &mut body)?;
+        self.leave_scope(&mut body)?;
 
         Ok(Some(definition::Function {
             name: function.name,
             params: function.params,
             return_type: function.return_type,
             body,
+            file: function.file,
         }))
     }
 
     fn generate_block(&mut self, block: nodes::Block, body: &mut Vec<definition::Instruction>) -> Result<(), errors::Error> {
+        self.owned_scopes.push(Vec::new());
+
         for item in block.items {
             match item {
                 nodes::BlockItem::Statement(statement) => {
@@ -55,6 +74,32 @@ impl IRGenerator {
                 nodes::BlockItem::Declaration(declaration) => {
                     self.generate_declaration(declaration, body)?;
                 }
+                nodes::BlockItem::Destructure(destructure) => {
+                    self.generate_destructure(destructure, body)?;
+                }
+            }
+        }
+
+        self.leave_scope(body)
+    }
+
+    fn leave_scope(&mut self, body: &mut Vec<definition::Instruction>) -> Result<(), errors::Error> {
+        let cleanups = self.owned_scopes.pop().unwrap();
+        for cleanup in cleanups.into_iter().rev() {
+            self.generate_cleanup(cleanup, body)?;
+        }
+
+        Ok(())
+    }
+
+    fn generate_cleanup(&mut self, cleanup: Cleanup, body: &mut Vec<definition::Instruction>) -> Result<(), errors::Error> {
+        match cleanup {
+            Cleanup::Free(name) => body.push(definition::Instruction::Free(definition::Val::Var(name))),
+            Cleanup::Deferred(statement) => {
+                // deferred code gets a scope of its own, it can't add to the one being left
+                self.owned_scopes.push(Vec::new());
+                self.generate_statement(statement, body)?;
+                self.leave_scope(body)?;
             }
         }
 
@@ -62,31 +107,110 @@ impl IRGenerator {
     }
 
     fn generate_statement(&mut self, statement: nodes::Statement, body: &mut Vec<definition::Instruction>) -> Result<(), errors::Error> {
+        body.push(definition::Instruction::Line(statement.line_started));
+
         match statement.kind {
             nodes::StatementKind::Return(expression) => {
-                let val = self.generate_expression(expression, body)?;
+                let mut val = None;
+                if let Some(expression) = expression {
+                    let ty = expression.ty.clone();
+                    val = Some((self.generate_expression(expression, body)?, ty));
+                }
+                // returning leaves every scope at once
+                let cleanups = self.owned_scopes.iter().rev().flat_map(|cleanups| cleanups.iter().rev()).cloned().collect::<Vec<_>>();
+                // deferred code could change a variable we're about to return, so hold on to the value first
+                let val = match val {
+                    Some((val, ty)) if cleanups.iter().any(|cleanup| matches!(cleanup, Cleanup::Deferred(_))) => {
+                        let dst = self.new_tmp_var(ty);
+                        body.push(definition::Instruction::Copy { src: val, dst: dst.clone() });
+                        Some(dst)
+                    }
+                    val => val.map(|(val, _)| val),
+                };
+                for cleanup in cleanups {
+                    self.generate_cleanup(cleanup, body)?;
+                }
                 body.push(definition::Instruction::Return(val));
             }
+            nodes::StatementKind::Assert(_, _) if self.release => {}
+            nodes::StatementKind::Assert(val, source) => {
+                let val = self.generate_expression(val, body)?;
+                let ok_label = self.new_tmp();
+
+                body.push(definition::Instruction::JumpIfNotZero(val, ok_label.clone()));
+                let message = format!("Error: {}\n{} | {}\n", errors::ErrorKind::AssertionFailed.text(), statement.line_started, source);
+                body.push(definition::Instruction::Trap(message));
+                body.push(definition::Instruction::Label(ok_label));
+            }
+            nodes::StatementKind::Defer(statement) => {
+                self.owned_scopes.last_mut().unwrap().push(Cleanup::Deferred(*statement));
+            }
             nodes::StatementKind::Block(block) => {
                 self.generate_block(block, body)?;
             }
             nodes::StatementKind::Expression(expression) => {
-                self.generate_expression(expression, body)?;
+                // a box that was only just made has nobody to take it, so get rid of it right away.
+                // one read out of a variable or through a pointer still belongs to whatever it came from
+                let is_fresh_box = matches!(expression.ty, nodes::Type::Box(_))
+                    && matches!(expression.kind, nodes::ExpressionKind::RequestRoom(_) | nodes::ExpressionKind::FunctionCall(..) | nodes::ExpressionKind::IndirectCall(..));
+                let val = self.generate_expression(expression, body)?;
+                if is_fresh_box {
+                    body.push(definition::Instruction::Free(val));
+                }
             }
-            nodes::StatementKind::If(val, block, else_block) => {
-                let val = self.generate_expression(val, body)?;
-                let label = self.new_tmp();
+            nodes::StatementKind::If(branches, else_block) => {
+                let end_label = self.new_tmp();
 
-                body.push(definition::Instruction::JumpIfZero(val, label.clone()));
-                self.generate_statement(*block, body)?;
-                body.push(definition::Instruction::Label(label));
+                for (val, block) in branches {
+                    let val = self.generate_expression(val, body)?;
+                    let next_label = self.new_tmp();
+
+                    body.push(definition::Instruction::JumpIfZero(val, next_label.clone()));
+                    self.generate_statement(block, body)?;
+                    body.push(definition::Instruction::Jump(end_label.clone()));
+                    body.push(definition::Instruction::Label(next_label));
+                }
 
                 if let Some(block) = else_block {
-                    let end_label = self.new_tmp();
+                    self.generate_statement(*block, body)?;
+                }
+
+                body.push(definition::Instruction::Label(end_label));
+            }
+            nodes::StatementKind::Switch(val, cases, default) => {
+                let val = self.generate_expression(val, body)?;
+                let end_label = self.new_tmp();
+                let default_label = self.new_tmp();
+
+                let mut switch_cases = Vec::new();
+                let mut case_labels = Vec::new();
+                for case in &cases {
+                    let label = self.new_tmp();
+                    let (low, high) = case.range();
+                    for value in low..=high {
+                        switch_cases.push((value, label.clone()));
+                    }
+                    case_labels.push(label);
+                }
+
+                body.push(definition::Instruction::Switch {
+                    val,
+                    cases: switch_cases,
+                    default: default_label.clone(),
+                });
+
+                for (case, label) in cases.into_iter().zip(case_labels) {
+                    body.push(definition::Instruction::Label(label));
+                    self.generate_statement(case.body, body)?;
                     body.push(definition::Instruction::Jump(end_label.clone()));
+                }
+
+                body.push(definition::Instruction::Label(default_label));
+                if let Some(block) = default {
                     self.generate_statement(*block, body)?;
-                    body.push(definition::Instruction::Label(end_label));
                 }
+
+                body.push(definition::Instruction::Label(end_label));
             }
             nodes::StatementKind::While(val, block) => {
                 let label = self.new_tmp();
@@ -99,12 +223,26 @@ impl IRGenerator {
                 body.push(definition::Instruction::Jump(label));
                 body.push(definition::Instruction::Label(end_label));
             }
+            nodes::StatementKind::DoWhile(block, val) => {
+                let label = self.new_tmp();
+
+                body.push(definition::Instruction::Label(label.clone()));
+                self.generate_statement(*block, body)?;
+                let val = self.generate_expression(val, body)?;
+                body.push(definition::Instruction::JumpIfNotZero(val, label));
+            }
+            nodes::StatementKind::StaticAssert(_) => unreachable!(),
         }
 
         Ok(())
     }
 
     fn generate_declaration(&mut self, declaration: nodes::Declaration, body: &mut Vec<definition::Instruction>) -> Result<(), errors::Error> {
+        if let nodes::Type::Box(_) = declaration.ty {
+            self.owned_scopes.last_mut().unwrap().push(Cleanup::Free(declaration.name.clone()));
+        }
+
+        body.push(definition::Instruction::Line(declaration.line_started));
         let val = self.generate_expression(declaration.value, body)?;
         body.push(definition::Instruction::Copy {
             src: val,
@@ -114,38 +252,91 @@ impl IRGenerator {
         Ok(())
     }
 
+    fn generate_destructure(&mut self, destructure: nodes::Destructure, body: &mut Vec<definition::Instruction>) -> Result<(), errors::Error> {
+        body.push(definition::Instruction::Line(destructure.line_started));
+        let val = self.generate_expression(destructure.value, body)?;
+        for (index, (name, _)) in destructure.names.into_iter().enumerate() {
+            body.push(definition::Instruction::TupleElement {
+                src: val.clone(),
+                index: index as u32,
+                dst: definition::Val::Var(name),
+            });
+        }
+
+        Ok(())
+    }
+
     fn generate_expression(&mut self, expression: nodes::Expression, body: &mut Vec<definition::Instruction>) -> Result<definition::Val, errors::Error> {
         match expression.kind {
             nodes::ExpressionKind::Number(n) => Ok(definition::Val::Number(n)),
             nodes::ExpressionKind::Binary(op, left, right) => {
+                let (left_ty, right_ty) = (left.ty.clone(), right.ty.clone());
                 let left = self.generate_expression(*left, body)?;
                 let right = self.generate_expression(*right, body)?;
 
                 let dst = self.new_tmp_var(expression.ty.clone());
-                let kind = match op {
-                    nodes::Binop::Add => definition::Binop::Add,
-                    nodes::Binop::Sub => definition::Binop::Sub,
-                    nodes::Binop::Mul => definition::Binop::Mul,
-                    nodes::Binop::Div => definition::Binop::Div,
-                    nodes::Binop::Mod => definition::Binop::Mod,
-                };
 
-                let instr = definition::Instruction::Binary {
-                    op: kind,
-                    src1: left,
-                    src2: right,
-                    dst: dst.clone(),
-                };
+                match (&op, &left_ty, &right_ty) {
+                    // moving a pointer along by some number of the things it points at
+                    (nodes::Binop::Add | nodes::Binop::Sub, nodes::Type::Pointer(_), nodes::Type::I32) | (nodes::Binop::Add, nodes::Type::I32, nodes::Type::Pointer(_)) => {
+                        let (ptr, index) = if let nodes::Type::Pointer(_) = left_ty { (left, right) } else { (right, left) };
+                        let index = if op == nodes::Binop::Sub {
+                            let negated = self.new_tmp_var(nodes::Type::I32);
+                            body.push(definition::Instruction::Binary {
+                                op: definition::Binop::Sub,
+                                src1: definition::Val::Number(0),
+                                src2: index,
+                                dst: negated.clone(),
+                            });
+                            negated
+                        } else {
+                            index
+                        };
 
-                body.push(instr);
+                        body.push(definition::Instruction::AddPtr { ptr, index, dst: dst.clone() });
+                    }
+                    (nodes::Binop::Sub, nodes::Type::Pointer(_), nodes::Type::Pointer(_)) => {
+                        body.push(definition::Instruction::PtrDiff { src1: left, src2: right, dst: dst.clone() });
+                    }
+                    _ => {
+                        let kind = match op {
+                            nodes::Binop::Add => definition::Binop::Add,
+                            nodes::Binop::Sub => definition::Binop::Sub,
+                            nodes::Binop::Mul => definition::Binop::Mul,
+                            nodes::Binop::Div => definition::Binop::Div,
+                            nodes::Binop::Mod => definition::Binop::Mod,
+                            nodes::Binop::Equal => definition::Binop::Equal,
+                            nodes::Binop::Less => definition::Binop::Less,
+                            nodes::Binop::Greater => definition::Binop::Greater,
+                        };
+
+                        let instr = definition::Instruction::Binary {
+                            op: kind,
+                            src1: left,
+                            src2: right,
+                            dst: dst.clone(),
+                        };
+
+                        body.push(instr);
+                    }
+                }
 
                 Ok(dst)
             }
+            nodes::ExpressionKind::Null => {
+                let dst = self.new_tmp_var(expression.ty.clone());
+                body.push(definition::Instruction::Null(dst.clone()));
+                Ok(dst)
+            }
             nodes::ExpressionKind::Assign(left, right) => {
                 let right = self.generate_expression(*right, body)?;
                 let left = match left.kind {
                     nodes::ExpressionKind::Variable(name) => {
                         let var = definition::Val::Var(name);
+                        // the old box is being replaced, so it has to go
+                        if let nodes::Type::Box(_) = left.ty {
+                            body.push(definition::Instruction::Free(var.clone()));
+                        }
                         body.push(definition::Instruction::Copy {
                             src: right,
                             dst: var.clone(),
@@ -194,10 +385,45 @@ impl IRGenerator {
             }
             nodes::ExpressionKind::FunctionCall(name, args) => {
                 let args = args.into_iter().map(|arg| self.generate_expression(arg, body)).collect::<Result<Vec<_>, _>>()?;
-                let dst = self.new_tmp_var(expression.ty.clone());
+                let dst = self.new_call_dst(expression.ty.clone());
 
                 body.push(definition::Instruction::FunctionCall(name, args, dst.clone()));
 
+                Ok(dst.unwrap_or(definition::Val::Number(0)))
+            }
+            nodes::ExpressionKind::IndirectCall(callee, args) => {
+                let ty = match &callee.ty {
+                    nodes::Type::Pointer(ty) => (**ty).clone(),
+                    _ => unreachable!(),
+                };
+                let ptr = self.generate_expression(*callee, body)?;
+                let args = args.into_iter().map(|arg| self.generate_expression(arg, body)).collect::<Result<Vec<_>, _>>()?;
+                let dst = self.new_call_dst(expression.ty.clone());
+
+                body.push(definition::Instruction::IndirectCall {
+                    ptr,
+                    ty,
+                    args,
+                    dst: dst.clone(),
+                });
+
+                Ok(dst.unwrap_or(definition::Val::Number(0)))
+            }
+            nodes::ExpressionKind::RequestRoom(expr) => {
+                let val = self.generate_expression(*expr, body)?;
+                let dst = self.new_tmp_var(expression.ty.clone());
+
+                body.push(definition::Instruction::Alloc(dst.clone()));
+                body.push(definition::Instruction::Store(val, dst.clone()));
+
+                Ok(dst)
+            }
+            nodes::ExpressionKind::Move(expr) => {
+                let val = self.generate_expression(*expr, body)?;
+                let dst = self.new_tmp_var(expression.ty.clone());
+
+                body.push(definition::Instruction::Move { src: val, dst: dst.clone() });
+
                 Ok(dst)
             }
             nodes::ExpressionKind::AddressOf(expr) => {
@@ -229,6 +455,15 @@ impl IRGenerator {
 
                 Ok(dst)
             }
+            nodes::ExpressionKind::SizeOf(_) => unreachable!(),
+            nodes::ExpressionKind::Tuple(elements) => {
+                let elements = elements.into_iter().map(|element| self.generate_expression(element, body)).collect::<Result<Vec<_>, _>>()?;
+                let dst = self.new_tmp_var(expression.ty.clone());
+
+                body.push(definition::Instruction::MakeTuple { elements, dst: dst.clone() });
+
+                Ok(dst)
+            }
             nodes::ExpressionKind::Variable(name) => Ok(definition::Val::Var(name)),
         }
     }
@@ -239,9 +474,339 @@ impl IRGenerator {
         definition::Val::Var(name)
     }
 
+    // calls to functions that return nothing don't get anywhere to put it. the type checker made
+    // sure those only show up as statements, so the placeholder value we hand back is never used
+    fn new_call_dst(&mut self, ty: nodes::Type) -> Option<definition::Val> {
+        match ty {
+            nodes::Type::Void => None,
+            ty => Some(self.new_tmp_var(ty)),
+        }
+    }
+
     fn new_tmp(&mut self) -> String {
         let name = format!(".tmp.{}", self.tmp_counter);
         self.tmp_counter += 1;
         name
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use super::*;
+    use crate::{parser, semantic_analysis, warnings, Options};
+    use super::definition::{Binop, Instruction, Val};
+
+    fn lower(file: &str) -> definition::Program {
+        lower_with(file, &Options::default())
+    }
+
+    fn lower_with(file: &str, options: &Options) -> definition::Program {
+        let input = std::fs::read_to_string(file).unwrap();
+        let program = parser::Parser::new(&input, &options.rules).unwrap().parse_program().unwrap();
+        let (program, symbol_table, _) = semantic_analysis::analyze(program, options).unwrap();
+        IRGenerator::new(symbol_table, options.release).generate_ir(program).unwrap()
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Value {
+        Int(i64),
+        Function(String),
+        Tuple(Vec<Value>),
+        Nothing,
+    }
+
+    impl Value {
+        fn int(&self) -> i64 {
+            match self {
+                Value::Int(n) => *n,
+                value => panic!("{:?} isn't a number", value),
+            }
+        }
+    }
+
+    // runs a lowered function on plain numbers, so tests can check what the ir does instead of how it looks
+    fn run(program: &definition::Program, name: &str, args: &[i64]) -> i64 {
+        call(program, name, args.iter().map(|arg| Value::Int(*arg)).collect()).int()
+    }
+
+    fn call(program: &definition::Program, name: &str, args: Vec<Value>) -> Value {
+        let function = program.functions.iter().find(|function| function.name == name).unwrap();
+        let labels: HashMap<&str, usize> = function.body.iter().enumerate().filter_map(|(i, instruction)| match instruction {
+            Instruction::Label(label) => Some((label.as_str(), i)),
+            _ => None,
+        }).collect();
+        let mut vars: HashMap<String, Value> = function.params.iter().map(|(param, _)| param.clone()).zip(args).collect();
+        let get = |vars: &HashMap<String, Value>, val: &Val| match val {
+            Val::Var(name) => vars[name].clone(),
+            Val::Number(n) => Value::Int(*n as i64),
+        };
+        let set = |vars: &mut HashMap<String, Value>, val: &Val, value: Value| match val {
+            Val::Var(name) => { vars.insert(name.clone(), value); }
+            Val::Number(_) => unreachable!(),
+        };
+
+        let mut pc = 0;
+        loop {
+            match &function.body[pc] {
+                Instruction::Copy { src, dst } => {
+                    let value = get(&vars, src);
+                    set(&mut vars, dst, value);
+                }
+                Instruction::Binary { op, src1, src2, dst } => {
+                    let (left, right) = (get(&vars, src1).int(), get(&vars, src2).int());
+                    let value = match op {
+                        Binop::Add => left + right,
+                        Binop::Sub => left - right,
+                        Binop::Mul => left * right,
+                        Binop::Div => left / right,
+                        Binop::Mod => left % right,
+                        Binop::Equal => (left == right) as i64,
+                        Binop::Less => (left < right) as i64,
+                        Binop::Greater => (left > right) as i64,
+                    };
+                    set(&mut vars, dst, Value::Int(value));
+                }
+                Instruction::Return(val) => return val.as_ref().map_or(Value::Nothing, |val| get(&vars, val)),
+                Instruction::Label(_) | Instruction::Line(_) => {}
+                Instruction::Jump(label) => pc = labels[label.as_str()],
+                Instruction::JumpIfZero(val, label) => if get(&vars, val).int() == 0 { pc = labels[label.as_str()] },
+                Instruction::JumpIfNotZero(val, label) => if get(&vars, val).int() != 0 { pc = labels[label.as_str()] },
+                Instruction::Switch { val, cases, default } => {
+                    let value = get(&vars, val).int();
+                    let label = cases.iter().find(|(case, _)| *case as i64 == value).map_or(default, |(_, label)| label);
+                    pc = labels[label.as_str()];
+                }
+                Instruction::FunctionCall(name, args, dst) => {
+                    let args = args.iter().map(|arg| get(&vars, arg)).collect();
+                    let value = call(program, name, args);
+                    if let Some(dst) = dst {
+                        set(&mut vars, dst, value);
+                    }
+                }
+                Instruction::IndirectCall { ptr, args, dst, .. } => {
+                    let name = match get(&vars, ptr) {
+                        Value::Function(name) => name,
+                        value => panic!("called {:?}", value),
+                    };
+                    let args = args.iter().map(|arg| get(&vars, arg)).collect();
+                    let value = call(program, &name, args);
+                    if let Some(dst) = dst {
+                        set(&mut vars, dst, value);
+                    }
+                }
+                Instruction::MakeTuple { elements, dst } => {
+                    let value = Value::Tuple(elements.iter().map(|element| get(&vars, element)).collect());
+                    set(&mut vars, dst, value);
+                }
+                Instruction::TupleElement { src, index, dst } => {
+                    let value = match get(&vars, src) {
+                        Value::Tuple(elements) => elements[*index as usize].clone(),
+                        value => panic!("{:?} isn't a tuple", value),
+                    };
+                    set(&mut vars, dst, value);
+                }
+                // the only addresses these tests take are of functions
+                Instruction::GetAddress(Val::Var(name), dst) => set(&mut vars, dst, Value::Function(name.clone())),
+                instruction => panic!("can't run {:?}", instruction),
+            }
+            pc += 1;
+        }
+    }
+
+    #[test]
+    fn test_else_if_picks_arm() {
+        let program = lower("ir_examples/branches.yl");
+        assert_eq!(run(&program, "pick", &[1, 7]), 10);
+        assert_eq!(run(&program, "pick", &[2, 7]), 20);
+        assert_eq!(run(&program, "pick", &[3, 7]), 7);
+    }
+
+    #[test]
+    fn test_switch_picks_case() {
+        let program = lower("ir_examples/branches.yl");
+        assert_eq!(run(&program, "swch", &[1, 7]), 10);
+        assert_eq!(run(&program, "swch", &[2, 7]), 20);
+        assert_eq!(run(&program, "swch", &[4, 7]), 20);
+        assert_eq!(run(&program, "swch", &[5, 7]), 7);
+        assert_eq!(run(&program, "swch", &[0, 7]), 7);
+    }
+
+    #[test]
+    fn test_switch_words_are_still_names() {
+        let program = lower("ir_examples/branches.yl");
+        assert_eq!(run(&program, "when", &[2, 3]), 10);
+    }
+
+    #[test]
+    fn test_do_while_runs_once() {
+        // the condition is already zero before the loop starts, the body still has to run one time
+        let program = lower("ir_examples/do_while.yl");
+        assert_eq!(run(&program, "once", &[0, 0]), 1);
+        assert_eq!(run(&program, "once", &[4, 9]), 5);
+        assert_eq!(run(&program, "keep", &[4, 9]), 13);
+    }
+
+    #[test]
+    fn test_function_pointers() {
+        let program = lower("ir_examples/function_pointers.yl");
+        assert_eq!(run(&program, "appl", &[1, 20]), 22);
+        assert_eq!(run(&program, "appl", &[0, 20]), 40);
+    }
+
+    #[test]
+    fn test_variadic_call_keeps_extras() {
+        let program = lower("ir_examples/variadic.yl");
+        let then = program.functions.iter().find(|function| function.name == "then").unwrap();
+        let args = then.body.iter().find_map(|instruction| match instruction {
+            Instruction::FunctionCall(name, args, _) if name == "printf" => Some(args),
+            _ => None,
+        }).unwrap();
+        assert_eq!(args.len(), 3);
+        assert!(matches!(&args[1], Val::Var(name) if semantic_analysis::source_name(name) == "argc"));
+        assert_eq!(run(&program, "some", &[2, 3]), 5);
+    }
+
+    fn traps(program: &definition::Program) -> Vec<&String> {
+        program.functions[0].body.iter().filter_map(|instruction| match instruction {
+            Instruction::Trap(message) => Some(message),
+            _ => None,
+        }).collect()
+    }
+
+    #[test]
+    fn test_assert_traps() {
+        let program = lower("ir_examples/assert.yl");
+        let traps = traps(&program);
+        assert_eq!(traps.len(), 1);
+        assert!(traps[0].ends_with("3 | i insist that counter minus 2 is not zero period\n"));
+    }
+
+    #[test]
+    fn test_release_drops_asserts() {
+        let options = Options { release: true, ..Options::default() };
+        assert!(traps(&lower_with("ir_examples/assert.yl", &options)).is_empty());
+    }
+
+    #[test]
+    fn test_unreachable_functions_left_out() {
+        // gone is only called by lost, and nothing calls lost
+        let program = lower("ir_examples/unreachable.yl");
+        let names = program.functions.iter().map(|function| function.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["main", "used"]);
+    }
+
+    #[test]
+    fn test_bare_box_statement() {
+        // the variable keeps its box until main returns, only the one nobody kept gets freed on the spot
+        let program = lower("ir_examples/bare_box.yl");
+        let freed = program.functions[0].body.iter()
+            .take_while(|instruction| !matches!(instruction, Instruction::Return(_)))
+            .filter_map(|instruction| match instruction {
+                Instruction::Free(Val::Var(name)) => Some(name.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(freed.len(), 2);
+        assert!(freed[0].starts_with(".tmp."));
+        assert_eq!(semantic_analysis::source_name(freed[1]), "boxxxxx");
+    }
+
+    #[test]
+    fn test_box_words_are_still_names() {
+        let program = lower("ir_examples/box_words.yl");
+        assert_eq!(run(&program, "box", &[3, 4]), 10);
+    }
+
+    #[test]
+    fn test_import_words_are_still_names() {
+        let program = lower("ir_examples/import_words.yl");
+        assert_eq!(run(&program, "calc", &[5, 4]), 11);
+    }
+
+    #[test]
+    fn test_tuple_return_and_destructure() {
+        // swap hands the pair back the other way round, and sprd pulls it apart into two variables
+        let program = lower("ir_examples/tuples.yl");
+        assert_eq!(run(&program, "sprd", &[3, 4]), 43);
+    }
+
+    #[test]
+    fn test_generic_instance() {
+        // only the copy of pick made for the call gets lowered, the template itself never does
+        let program = lower("ir_examples/generics.yl");
+        let names = program.functions.iter().map(|function| function.name.as_str()).collect::<Vec<_>>();
+        assert!(names.contains(&"pick<i32>"));
+        assert!(!names.contains(&"pick"));
+        assert_eq!(run(&program, "frst", &[3, 4]), 3);
+    }
+
+    #[test]
+    fn test_constant_words_are_still_names() {
+        let program = lower("ir_examples/constant_words.yl");
+        assert_eq!(run(&program, "cnst", &[1, 3]), 40);
+    }
+
+    #[test]
+    fn test_please_is_still_a_name() {
+        // the first please is a parameter being assigned, the second one is just being nice
+        let program = lower("ir_examples/please_words.yl");
+        assert_eq!(run(&program, "plse", &[1, 3]), 8);
+    }
+
+    #[test]
+    fn test_defer_order_on_early_return() {
+        // the early return runs both deferred calls itself, the one written last going first
+        let program = lower("ir_examples/defer.yl");
+        let erly = program.functions.iter().find(|function| function.name == "erly").unwrap();
+        let marks = erly.body.iter()
+            .take_while(|instruction| !matches!(instruction, Instruction::Return(_)))
+            .filter_map(|instruction| match instruction {
+                Instruction::FunctionCall(name, args, _) if name == "mark" => Some(args[0].clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert!(matches!(marks[..], [Val::Number(2), Val::Number(1)]));
+        assert_eq!(run(&program, "erly", &[0, 9]), 10);
+        assert_eq!(run(&program, "erly", &[1, 9]), 1);
+    }
+
+    #[test]
+    fn test_nothing_is_still_a_name() {
+        // a call to a function that returns nothing has nowhere to put its result
+        let program = lower("ir_examples/nothing.yl");
+        let wrap = program.functions.iter().find(|function| function.name == "wrap").unwrap();
+        assert!(wrap.body.iter().any(|instruction| matches!(instruction, Instruction::FunctionCall(name, _, None) if name == "skip")));
+        assert_eq!(run(&program, "wrap", &[2, 3]), 5);
+    }
+
+    #[test]
+    fn test_size_of_is_folded() {
+        // the sizes are numbers by the time the ir exists, so running it needs no target at all
+        let program = lower("ir_examples/size_of.yl");
+        assert_eq!(run(&program, "mesr", &[0, 1]), 1217);
+    }
+
+    #[test]
+    fn test_comparison_words_are_still_names() {
+        let program = lower("ir_examples/compare_words.yl");
+        assert_eq!(run(&program, "cmpr", &[2, 3, 9]), 9);
+        assert_eq!(run(&program, "cmpr", &[3, 3, 9]), 1);
+        assert_eq!(run(&program, "cmpr", &[4, 3, 9]), 100);
+    }
+
+    #[test]
+    fn test_every_branch_returns() {
+        // nothing after the if is needed when every way through it has already returned
+        let program = lower("ir_examples/every_branch_returns.yl");
+        assert_eq!(program.functions.len(), 2);
+    }
+
+    #[test]
+    fn test_unreachable_code_warns() {
+        let input = std::fs::read_to_string("ir_examples/unreachable_code.yl").unwrap();
+        let options = Options::default();
+        let program = parser::Parser::new(&input, &options.rules).unwrap().parse_program().unwrap();
+        let (_, _, found) = semantic_analysis::analyze(program, &options).unwrap();
+        assert!(matches!(found[..], [warnings::Warning { kind: warnings::WarningKind::UnreachableCode, line: 4, .. }]));
+    }
+}
@@ -0,0 +1,224 @@
+use std::collections::HashSet;
+use crate::errors;
+use crate::semantic_analysis;
+use super::definition::{self, Instruction, Val};
+use super::flow::{self, Graph};
+
+// makes sure nothing gets read before something was put in it. works on the ir since that's where
+// every path through a function is spelled out as jumps. a variable counts as assigned when every
+// path to the read assigns it, and a pointer's target counts as empty when any path leaves it empty
+pub struct DefiniteAssignmentChecker;
+
+#[derive(Clone, PartialEq)]
+struct State {
+    assigned: HashSet<String>,
+    // pointers to memory nothing has been stored into yet
+    empty_targets: HashSet<String>,
+}
+
+impl DefiniteAssignmentChecker {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn check_program(&mut self, program: &definition::Program) -> Result<(), errors::Error> {
+        for function in &program.functions {
+            self.check_function(function).map_err(|e| e.in_file(function.file.clone()))?;
+        }
+
+        Ok(())
+    }
+
+    fn check_function(&mut self, function: &definition::Function) -> Result<(), errors::Error> {
+        let graph = Graph::new(&function.body);
+
+        // only things the function writes to are its own. anything else is a function or lives somewhere else
+        let locals = function.body.iter().flat_map(flow::writes).collect::<HashSet<_>>();
+
+        let entry = State {
+            assigned: function.params.iter().map(|(name, _)| name.clone()).collect(),
+            empty_targets: HashSet::new(),
+        };
+
+        // what's known at the start of each block, None until some path gets there
+        let mut starts: Vec<Option<State>> = vec![None; graph.blocks.len()];
+        if !starts.is_empty() {
+            starts[0] = Some(entry);
+        }
+
+        let mut worklist = (0..graph.blocks.len()).collect::<Vec<_>>();
+        while let Some(block) = worklist.pop() {
+            let mut state = match &starts[block] {
+                Some(state) => state.clone(),
+                None => continue,
+            };
+
+            let (start, end) = graph.blocks[block];
+            for instruction in &function.body[start..end] {
+                transfer(instruction, &mut state);
+            }
+
+            for &successor in &graph.successors[block] {
+                let merged = match &starts[successor] {
+                    Some(old) => State {
+                        assigned: old.assigned.intersection(&state.assigned).cloned().collect(),
+                        empty_targets: old.empty_targets.union(&state.empty_targets).cloned().collect(),
+                    },
+                    None => state.clone(),
+                };
+
+                if starts[successor].as_ref() != Some(&merged) {
+                    starts[successor] = Some(merged);
+                    worklist.push(successor);
+                }
+            }
+        }
+
+        // now that every block knows what it starts with, walk through them in order looking for bad reads
+        let mut line = 0;
+        for (block, &(start, end)) in graph.blocks.iter().enumerate() {
+            // None means nothing jumps here, so nothing in it ever runs
+            let mut state = starts[block].clone();
+
+            for instruction in &function.body[start..end] {
+                if let Instruction::Line(number) = instruction {
+                    line = *number;
+                }
+
+                let state = match &mut state {
+                    Some(state) => state,
+                    None => continue,
+                };
+
+                for name in flow::reads(instruction) {
+                    if locals.contains(&name) && !state.assigned.contains(&name) {
+                        let name = semantic_analysis::source_name(&name).to_string();
+                        return Err(errors::Error::new(errors::ErrorKind::MaybeUnassigned(name), line));
+                    }
+                }
+
+                if let Instruction::Load(Val::Var(ptr), _) = instruction {
+                    if state.empty_targets.contains(ptr) {
+                        let name = format!("what {} is pointing at", semantic_analysis::source_name(ptr));
+                        return Err(errors::Error::new(errors::ErrorKind::MaybeUnassigned(name), line));
+                    }
+                }
+
+                transfer(instruction, state);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn transfer(instruction: &Instruction, state: &mut State) {
+    match instruction {
+        // the memory behind a fresh allocation has nothing in it yet
+        Instruction::Alloc(Val::Var(dst)) => {
+            state.empty_targets.insert(dst.clone());
+        }
+        Instruction::Copy { src, dst: Val::Var(dst) } | Instruction::Move { src, dst: Val::Var(dst) } => {
+            if flow::var(src).is_some_and(|src| state.empty_targets.contains(&src)) {
+                state.empty_targets.insert(dst.clone());
+            } else {
+                state.empty_targets.remove(dst);
+            }
+        }
+        Instruction::Store(_, Val::Var(ptr)) => {
+            state.empty_targets.remove(ptr);
+        }
+        // we can't follow the pointer once it's taken, so whatever it points at might get filled in through it
+        Instruction::GetAddress(Val::Var(src), _) => {
+            state.assigned.insert(src.clone());
+        }
+        // a function handed a pointer might be the one filling it in
+        Instruction::FunctionCall(_, args, _) | Instruction::IndirectCall { args, .. } => {
+            for arg in args {
+                if let Val::Var(name) = arg {
+                    state.empty_targets.remove(name);
+                }
+            }
+        }
+        _ => {}
+    }
+
+    for name in flow::writes(instruction) {
+        if !matches!(instruction, Instruction::Alloc(_) | Instruction::Copy { .. } | Instruction::Move { .. }) {
+            state.empty_targets.remove(&name);
+        }
+        state.assigned.insert(name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(body: Vec<Instruction>) -> Result<(), errors::Error> {
+        let function = definition::Function {
+            name: "main".to_string(),
+            params: vec![("argc.1".to_string(), definition::Type::I32)],
+            return_type: definition::Type::I32,
+            body,
+            file: None,
+        };
+        DefiniteAssignmentChecker::new().check_program(&definition::Program { functions: vec![function] })
+    }
+
+    fn var(name: &str) -> Val {
+        Val::Var(name.to_string())
+    }
+
+    #[test]
+    fn test_assigned_on_one_branch() {
+        let result = check(vec![
+            Instruction::Line(2),
+            Instruction::JumpIfZero(var("argc.1"), "skip".to_string()),
+            Instruction::Copy { src: Val::Number(1), dst: var("picked.2") },
+            Instruction::Label("skip".to_string()),
+            Instruction::Line(3),
+            Instruction::Return(Some(var("picked.2"))),
+        ]);
+        assert_eq!(result.unwrap_err().kind, errors::ErrorKind::MaybeUnassigned("picked".to_string()));
+    }
+
+    #[test]
+    fn test_assigned_on_every_branch() {
+        let result = check(vec![
+            Instruction::JumpIfZero(var("argc.1"), "other".to_string()),
+            Instruction::Copy { src: Val::Number(1), dst: var("picked.2") },
+            Instruction::Jump("done".to_string()),
+            Instruction::Label("other".to_string()),
+            Instruction::Copy { src: Val::Number(2), dst: var("picked.2") },
+            Instruction::Label("done".to_string()),
+            Instruction::Return(Some(var("picked.2"))),
+        ]);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_load_before_store() {
+        let result = check(vec![
+            Instruction::Line(2),
+            Instruction::Alloc(var("boxxxxx.2")),
+            Instruction::Line(3),
+            Instruction::Load(var("boxxxxx.2"), var(".tmp.0")),
+            Instruction::Return(Some(var(".tmp.0"))),
+        ]);
+        let error = result.unwrap_err();
+        assert_eq!(error.kind, errors::ErrorKind::MaybeUnassigned("what boxxxxx is pointing at".to_string()));
+        assert_eq!(error.line, 3);
+    }
+
+    #[test]
+    fn test_load_after_store() {
+        let result = check(vec![
+            Instruction::Alloc(var("boxxxxx.2")),
+            Instruction::Store(var("argc.1"), var("boxxxxx.2")),
+            Instruction::Load(var("boxxxxx.2"), var(".tmp.0")),
+            Instruction::Return(Some(var(".tmp.0"))),
+        ]);
+        assert!(result.is_ok());
+    }
+}
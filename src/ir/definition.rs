@@ -13,6 +13,7 @@ pub struct Function {
     pub params: Vec<(String, Type)>,
     pub return_type: Type,
     pub body: Vec<Instruction>,
+    pub file: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -27,12 +28,26 @@ pub enum Instruction {
         src2: Val,
         dst: Val,
     },
-    Return(Val),
+    Return(Option<Val>),
     Label(String),
+    // the source line the instructions after it came from, for errors found after lowering
+    Line(usize),
     Jump(String),
     JumpIfZero(Val, String),
     JumpIfNotZero(Val, String),
-    FunctionCall(String, Vec<Val>, Val),
+    Switch {
+        val: Val,
+        cases: Vec<(u64, String)>,
+        default: String,
+    },
+    // no destination when the function returns nothing
+    FunctionCall(String, Vec<Val>, Option<Val>),
+    IndirectCall {
+        ptr: Val,
+        ty: Type,
+        args: Vec<Val>,
+        dst: Option<Val>,
+    },
     GetAddress(Val, Val),
     Store(Val, Val),
     Load(Val, Val),
@@ -40,7 +55,33 @@ pub enum Instruction {
         ptr: Val,
         index: Val,
         dst: Val,
-    }
+    },
+    // how many of the pointed at things lie between src2 and src1
+    PtrDiff {
+        src1: Val,
+        src2: Val,
+        dst: Val,
+    },
+    // stores a pointer to nowhere in dst
+    Null(Val),
+    MakeTuple {
+        elements: Vec<Val>,
+        dst: Val,
+    },
+    TupleElement {
+        src: Val,
+        index: u32,
+        dst: Val,
+    },
+    // prints the message and stops the program
+    Trap(String),
+    Alloc(Val),
+    Free(Val),
+    // copy src into dst and leave src empty, so freeing src afterwards does nothing
+    Move {
+        src: Val,
+        dst: Val,
+    },
 }
 
 #[derive(Debug, Clone)]
@@ -51,6 +92,8 @@ pub enum Binop {
     Div,
     Mod,
     Equal,
+    Less,
+    Greater,
 }
 
 #[derive(Debug, Clone)]
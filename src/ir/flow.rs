@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+use super::definition::{Instruction, Val};
+
+// a function body cut up into basic blocks, runs of instructions that only get entered at the top
+// and only leave at the bottom. the passes that care about every path through a function work on this
+pub struct Graph {
+    // start of each block, and where the block after it starts
+    pub blocks: Vec<(usize, usize)>,
+    pub successors: Vec<Vec<usize>>,
+}
+
+impl Graph {
+    // a new block starts at every label and right after every jump
+    pub fn new(body: &[Instruction]) -> Self {
+        let mut blocks = Vec::new();
+        let mut labels = HashMap::new();
+
+        let mut start = 0;
+        for (i, instruction) in body.iter().enumerate() {
+            if let Instruction::Label(label) = instruction {
+                if i > start {
+                    blocks.push((start, i));
+                    start = i;
+                }
+                labels.insert(label.clone(), blocks.len());
+            }
+
+            if ends_block(instruction) {
+                blocks.push((start, i + 1));
+                start = i + 1;
+            }
+        }
+
+        if start < body.len() || blocks.is_empty() {
+            blocks.push((start, body.len()));
+        }
+
+        let successors = (0..blocks.len()).map(|block| {
+            let (start, end) = blocks[block];
+            let next = if block + 1 < blocks.len() { vec![block + 1] } else { Vec::new() };
+
+            if end == start {
+                return next;
+            }
+
+            match &body[end - 1] {
+                Instruction::Jump(label) => vec![labels[label]],
+                Instruction::JumpIfZero(_, label) | Instruction::JumpIfNotZero(_, label) => {
+                    let mut successors = next;
+                    successors.push(labels[label]);
+                    successors
+                }
+                Instruction::Switch { cases, default, .. } => {
+                    let mut successors = cases.iter().map(|(_, label)| labels[label]).collect::<Vec<_>>();
+                    successors.push(labels[default]);
+                    successors
+                }
+                Instruction::Return(_) | Instruction::Trap(_) => Vec::new(),
+                _ => next,
+            }
+        }).collect();
+
+        Self { blocks, successors }
+    }
+
+    // which blocks some path from the top of the function gets to
+    pub fn reachable(&self) -> Vec<bool> {
+        let mut reachable = vec![false; self.blocks.len()];
+        let mut worklist = vec![0];
+        while let Some(block) = worklist.pop() {
+            if !reachable[block] {
+                reachable[block] = true;
+                worklist.extend(&self.successors[block]);
+            }
+        }
+        reachable
+    }
+}
+
+fn ends_block(instruction: &Instruction) -> bool {
+    matches!(instruction, Instruction::Jump(_) | Instruction::JumpIfZero(..) | Instruction::JumpIfNotZero(..)
+        | Instruction::Switch { .. } | Instruction::Return(_) | Instruction::Trap(_))
+}
+
+pub fn var(val: &Val) -> Option<String> {
+    match val {
+        Val::Var(name) => Some(name.clone()),
+        Val::Number(_) => None,
+    }
+}
+
+pub fn reads(instruction: &Instruction) -> Vec<String> {
+    let vals: Vec<&Val> = match instruction {
+        Instruction::Copy { src, .. } | Instruction::Move { src, .. } | Instruction::TupleElement { src, .. } => vec![src],
+        Instruction::Binary { src1, src2, .. } | Instruction::PtrDiff { src1, src2, .. } => vec![src1, src2],
+        Instruction::Return(val) => val.iter().collect(),
+        Instruction::JumpIfZero(val, _) | Instruction::JumpIfNotZero(val, _) | Instruction::Switch { val, .. } => vec![val],
+        Instruction::FunctionCall(_, args, _) => args.iter().collect(),
+        Instruction::IndirectCall { ptr, args, .. } => std::iter::once(ptr).chain(args.iter()).collect(),
+        Instruction::Store(src, ptr) => vec![src, ptr],
+        Instruction::Load(ptr, _) => vec![ptr],
+        Instruction::AddPtr { ptr, index, .. } => vec![ptr, index],
+        Instruction::MakeTuple { elements, .. } => elements.iter().collect(),
+        Instruction::Free(val) => vec![val],
+        // taking an address isn't reading what's there
+        Instruction::GetAddress(..) | Instruction::Null(_) | Instruction::Alloc(_) | Instruction::Trap(_)
+            | Instruction::Label(_) | Instruction::Line(_) | Instruction::Jump(_) => Vec::new(),
+    };
+
+    vals.into_iter().filter_map(var).collect()
+}
+
+pub fn writes(instruction: &Instruction) -> Vec<String> {
+    let vals: Vec<&Val> = match instruction {
+        Instruction::Copy { dst, .. } | Instruction::Move { dst, .. } | Instruction::TupleElement { dst, .. }
+            | Instruction::Binary { dst, .. } | Instruction::PtrDiff { dst, .. } | Instruction::Load(_, dst)
+            | Instruction::AddPtr { dst, .. } | Instruction::MakeTuple { dst, .. } | Instruction::GetAddress(_, dst)
+            | Instruction::Null(dst) | Instruction::Alloc(dst) => vec![dst],
+        Instruction::FunctionCall(_, _, dst) | Instruction::IndirectCall { dst, .. } => dst.iter().collect(),
+        _ => Vec::new(),
+    };
+
+    vals.into_iter().filter_map(var).collect()
+}
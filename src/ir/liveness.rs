@@ -0,0 +1,98 @@
+use std::collections::HashSet;
+use crate::semantic_analysis;
+use crate::warnings;
+use super::definition::{self, Instruction};
+use super::flow::{self, Graph};
+
+// finds values that get put in a variable and then never read, because every path from there either
+// overwrites the variable or ends first. a variable is live at a point if some path from it reads it
+pub struct LivenessChecker {
+    pub warnings: Vec<warnings::Warning>,
+}
+
+impl LivenessChecker {
+    pub fn new() -> Self {
+        Self { warnings: Vec::new() }
+    }
+
+    pub fn check_program(&mut self, program: &definition::Program) {
+        for function in &program.functions {
+            self.check_function(function);
+        }
+    }
+
+    fn check_function(&mut self, function: &definition::Function) {
+        let graph = Graph::new(&function.body);
+        let reachable = graph.reachable();
+
+        // a variable with its address taken can be read through the pointer, and one that's never read
+        // at all already gets told off for being unused. temporaries start with a dot and are ours
+        let address_taken = function.body.iter().filter_map(|instruction| match instruction {
+            Instruction::GetAddress(src, _) => flow::var(src),
+            _ => None,
+        }).collect::<HashSet<_>>();
+        let read = function.body.iter().flat_map(flow::reads).collect::<HashSet<_>>();
+        let watched = |name: &String| !name.starts_with('.') && read.contains(name) && !address_taken.contains(name);
+
+        // what's live at the start of each block, grown until nothing changes
+        let mut live_in = vec![HashSet::new(); graph.blocks.len()];
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for block in (0..graph.blocks.len()).rev() {
+                let live = self.walk_block(function, &graph, &live_in, block, None);
+                if live != live_in[block] {
+                    live_in[block] = live;
+                    changed = true;
+                }
+            }
+        }
+
+        let mut found = Vec::new();
+        for block in (0..graph.blocks.len()).filter(|&block| reachable[block]) {
+            self.walk_block(function, &graph, &live_in, block, Some(&mut found));
+        }
+        found.retain(|(_, name)| watched(name));
+
+        // deferred code shows up once for every way out of its block, but it only needs saying once
+        found.sort();
+        found.dedup();
+        for (line, name) in found {
+            let name = semantic_analysis::source_name(&name).to_string();
+            self.warnings.push(warnings::Warning::new(warnings::WarningKind::DeadAssignment(name), line).in_file(function.file.clone()));
+        }
+    }
+
+    // goes backwards through a block from what's live at its end, noting down in `dead` every assignment
+    // to a variable that isn't live right after it. gives back what's live at the start
+    fn walk_block(&self, function: &definition::Function, graph: &Graph, live_in: &[HashSet<String>], block: usize, mut dead: Option<&mut Vec<(usize, String)>>) -> HashSet<String> {
+        let mut live = graph.successors[block].iter().flat_map(|&successor| live_in[successor].iter().cloned()).collect::<HashSet<_>>();
+
+        let (start, end) = graph.blocks[block];
+        for i in (start..end).rev() {
+            let instruction = &function.body[i];
+
+            for name in flow::writes(instruction) {
+                if let Some(dead) = &mut dead {
+                    let assignment = matches!(instruction, Instruction::Copy { .. } | Instruction::Move { .. } | Instruction::TupleElement { .. });
+                    if assignment && !live.contains(&name) {
+                        dead.push((line_at(&function.body, i), name.clone()));
+                    }
+                }
+                live.remove(&name);
+            }
+
+            live.extend(flow::reads(instruction));
+        }
+
+        live
+    }
+}
+
+// the line the instruction at `index` came from
+fn line_at(body: &[Instruction], index: usize) -> usize {
+    body[..=index].iter().rev().find_map(|instruction| match instruction {
+        Instruction::Line(line) => Some(*line),
+        _ => None,
+    }).unwrap_or(0)
+}
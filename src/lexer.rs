@@ -2,12 +2,26 @@
 
 use crate::errors;
 
+// comments are spelled out like everything else. a note can go over several lines and contain
+// anything at all, a side note lasts until the end of its line.
+pub const NOTE_START: &str = "note to self";
+pub const NOTE_END: &str = "end of note";
+pub const SIDE_NOTE: &str = "side note";
+
 #[derive(Debug, Clone)]
 pub struct Lexer<'a> {
     pub input: &'a str,
     line: usize,
     position: usize,
     current_char: char,
+    // skipped by the parser, kept around for anything that wants to show the code to a human
+    pub comments: Vec<Comment>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Comment {
+    pub text: String,
+    pub line: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -157,25 +171,69 @@ impl<'a> Lexer<'a> {
             line: 1,
             position: 0,
             current_char: if let Some(c) = input.chars().nth(0) { c } else { '\0' },
+            comments: Vec::new(),
         }
     }
 
+    // position is in bytes so comments can have whatever characters they want in them
     fn advance(&mut self) {
-        self.position += 1;
-        if self.position < self.input.len() {
-            self.current_char = self.input.chars().nth(self.position).unwrap();
-        } else {
-            self.current_char = '\0';
+        if self.current_char == '\0' {
+            return;
         }
+        self.position += self.current_char.len_utf8();
+        self.current_char = self.input[self.position..].chars().next().unwrap_or('\0');
     }
 
-    pub fn next_token(&mut self) -> Result<Token, errors::Error> {
+    fn advance_by(&mut self, text: &str) {
+        for _ in text.chars() {
+            self.advance();
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
         while self.current_char.is_whitespace() {
             if self.current_char == '\n' {
                 self.line += 1;
             }
             self.advance();
         }
+    }
+
+    fn skip_comments(&mut self) -> Result<(), errors::Error> {
+        loop {
+            self.skip_whitespace();
+
+            let line = self.line;
+            if phrase_at(self.input, self.position, NOTE_START) {
+                self.advance_by(NOTE_START);
+                let start = self.position;
+                while !phrase_at(self.input, self.position, NOTE_END) {
+                    match self.current_char {
+                        '\0' => return Err(errors::Error::new(errors::ErrorKind::UnexpectedEOF, line)),
+                        '\n' => self.line += 1,
+                        _ => {}
+                    }
+                    self.advance();
+                }
+                let text = self.input[start..self.position].trim().to_string();
+                self.advance_by(NOTE_END);
+                self.comments.push(Comment { text, line });
+            } else if phrase_at(self.input, self.position, SIDE_NOTE) {
+                self.advance_by(SIDE_NOTE);
+                let start = self.position;
+                while self.current_char != '\n' && self.current_char != '\0' {
+                    self.advance();
+                }
+                let text = self.input[start..self.position].trim().to_string();
+                self.comments.push(Comment { text, line });
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    pub fn next_token(&mut self) -> Result<Token, errors::Error> {
+        self.skip_comments()?;
 
         let line = self.line;
 
@@ -271,8 +329,56 @@ impl<'a> Lexer<'a> {
         })
     }
 
+    // looks ahead on a copy that starts without any comments, the ones it skips would only be thrown away
     pub fn peek_token(&self) -> Result<Token, errors::Error> {
-        let mut lexer = self.clone();
+        let mut lexer = Lexer {
+            input: self.input,
+            line: self.line,
+            position: self.position,
+            current_char: self.current_char,
+            comments: Vec::new(),
+        };
         lexer.next_token()
     }
-}
\ No newline at end of file
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+// true if `phrase` is at byte `position` of `input` as whole words
+pub fn phrase_at(input: &str, position: usize, phrase: &str) -> bool {
+    let rest = &input[position..];
+    if !rest.starts_with(phrase) {
+        return false;
+    }
+
+    let starts_word = input[..position].chars().next_back().is_none_or(|c| !is_word_char(c));
+    let ends_word = rest[phrase.len()..].chars().next().is_none_or(|c| !is_word_char(c));
+    starts_word && ends_word
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_comments_are_kept() {
+        let input = "return note to self\nhello\nthere end of note 1 side note the answer\nperiod";
+        let mut lexer = Lexer::new(input);
+        let mut kinds = Vec::new();
+        loop {
+            let token = lexer.next_token().unwrap();
+            if token.kind == TokenKind::EOF {
+                break;
+            }
+            // peeking shouldn't read the same comment in a second time
+            lexer.peek_token().unwrap();
+            kinds.push(token.kind);
+        }
+
+        assert_eq!(kinds, vec![TokenKind::Keyword(Keyword::Return), TokenKind::Number(1), TokenKind::Keyword(Keyword::Period)]);
+        let comments = lexer.comments.iter().map(|comment| (comment.text.as_str(), comment.line)).collect::<Vec<_>>();
+        assert_eq!(comments, vec![("hello\nthere", 1), ("the answer", 3)]);
+    }
+}
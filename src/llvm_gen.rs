@@ -1,14 +1,20 @@
 use inkwell::{
-    context::Context, passes::PassManager, targets::{FileType, InitializationConfig, RelocMode, Target, TargetMachine}, types::BasicType, OptimizationLevel
+    context::Context, passes::PassManager, targets::{FileType, InitializationConfig, RelocMode, Target, TargetData, TargetMachine}, types::{AnyType, BasicType}, attributes::{Attribute, AttributeLoc}, OptimizationLevel
 };
 
-use std::{collections::HashMap, process::Command};
+use std::{collections::HashMap, convert::TryFrom, process::Command};
 
 mod var_collecter;
 
+use crate::errors;
 use crate::ir::definition;
 use crate::semantic_analysis::typecheck::SymbolTable;
 
+const RUNTIME_SOURCE: &str = include_str!("../runtime/yeller_runtime.c");
+
+// tuples bigger than this don't fit in the return registers, so they get written through a hidden pointer instead
+const MAX_DIRECT_RETURN_SIZE: u64 = 16;
+
 pub struct LLVMGenerator<'a> {
     symbol_table: HashMap<String, inkwell::values::PointerValue<'a>>,
     label_table: HashMap<String, inkwell::basic_block::BasicBlock<'a>>,
@@ -16,37 +22,68 @@ pub struct LLVMGenerator<'a> {
     module: inkwell::module::Module<'a>,
     current_function: String,
     frontend_symbol_table: &'a SymbolTable,
+    // where the current function writes its return value if it's too big to return normally
+    sret: Option<inkwell::values::PointerValue<'a>>,
+    target_machine: TargetMachine,
+    target_data: TargetData,
 }
 
 fn __ty_to_llvm_ty<'a>(ctx: &'a inkwell::context::Context, ty: &definition::Type) -> inkwell::types::BasicTypeEnum<'a> {
     match ty {
         definition::Type::I32 => ctx.i32_type().as_basic_type_enum(),
+        definition::Type::Pointer(box definition::Type::Function(params, ret_ty, variadic)) => {
+            let params = params.iter().map(|ty| __ty_to_llvm_ty(ctx, ty).into()).collect::<Vec<_>>();
+            let fn_type = match ret_ty {
+                box definition::Type::Void => ctx.void_type().fn_type(&params, *variadic),
+                _ => __ty_to_llvm_ty(ctx, ret_ty).fn_type(&params, *variadic),
+            };
+            fn_type.ptr_type(inkwell::AddressSpace::from(0)).as_basic_type_enum()
+        }
+        definition::Type::Tuple(elements) => {
+            let elements = elements.iter().map(|ty| __ty_to_llvm_ty(ctx, ty)).collect::<Vec<_>>();
+            ctx.struct_type(&elements, false).as_basic_type_enum()
+        }
+        definition::Type::Box(box inner_ty) |
         definition::Type::Pointer(box inner_ty) => {
             let inner_ty = __ty_to_llvm_ty(ctx, inner_ty);
             inner_ty.ptr_type(inkwell::AddressSpace::from(0)).as_basic_type_enum()
         }
-        definition::Type::Function(_, _) => unreachable!(),
+        definition::Type::Function(..) | definition::Type::Void | definition::Type::Param(_) => unreachable!(),
     }
 }
 
-#[allow(dead_code)]
-pub fn sizeof_type(t: &definition::Type) -> u64 {
+// the machine we're compiling for, which is the one we're running on. anything that cares about
+// sizes and layouts asks this one so they all agree
+pub fn target_machine() -> TargetMachine {
     Target::initialize_all(&InitializationConfig::default());
     let target = Target::from_triple(&TargetMachine::get_default_triple()).unwrap();
-    let target_machine = target
+    target
         .create_target_machine(
             &TargetMachine::get_default_triple(),
             "generic",
             "",
             OptimizationLevel::Aggressive,
-            RelocMode::Default,
+            RelocMode::PIC,
             inkwell::targets::CodeModel::Default,
         )
-        .unwrap();
-    let target_data = target_machine.get_target_data();
-    // generate a ctx temporarily
-    let ctx = Context::create();
-    target_data.get_abi_size(&__ty_to_llvm_ty(&ctx, t))
+        .unwrap()
+}
+
+// how big things are on the target, for anything that needs to know before there's a module to ask.
+// setting up the target isn't cheap, so make one of these and keep it around
+pub struct TypeSizes {
+    target_data: TargetData,
+    context: Context,
+}
+
+impl TypeSizes {
+    pub fn new() -> Self {
+        Self { target_data: target_machine().get_target_data(), context: Context::create() }
+    }
+
+    pub fn size_of(&self, t: &definition::Type) -> u64 {
+        self.target_data.get_abi_size(&__ty_to_llvm_ty(&self.context, t))
+    }
 }
 
 impl<'a> LLVMGenerator<'a> {
@@ -56,6 +93,8 @@ impl<'a> LLVMGenerator<'a> {
 
     pub fn new(context: &'a Context, frontend_symbol_table: &'a SymbolTable) -> Self {
         let module = context.create_module("main");
+        let target_machine = target_machine();
+        let target_data = target_machine.get_target_data();
         Self {
             symbol_table: HashMap::new(),
             label_table: HashMap::new(),
@@ -63,42 +102,31 @@ impl<'a> LLVMGenerator<'a> {
             module,
             current_function: String::new(),
             frontend_symbol_table,
+            sret: None,
+            target_machine,
+            target_data,
         }
     }
 
-    pub fn generate(mut self, program: definition::Program, output_file: &str) {
-        Target::initialize_all(&InitializationConfig::default());
+    pub fn generate(mut self, program: definition::Program, output_file: &str) -> Result<(), errors::Error> {
+        // Set up the target machine for the host
+        self.module.set_triple(&self.target_machine.get_triple());
+        self.module.set_data_layout(&self.target_data.get_data_layout());
 
         for f in program.functions {
             self.generate_function(f) 
         }
-    
-        // Set up the target machine for the host
-        let target = Target::from_triple(&TargetMachine::get_default_triple()).unwrap();
-        let target_machine = target
-            .create_target_machine(
-                &TargetMachine::get_default_triple(),
-                "generic",
-                "",
-                OptimizationLevel::Aggressive,
-                RelocMode::Default,
-                inkwell::targets::CodeModel::Default,
-            )
-            .unwrap();
 
         // Print out the generated IR
         self.module.print_to_file("output.ll").expect("Failed to print module to file");
 
-        match self.module.verify() {
-            Ok(_) => {}
-            Err(err) => {
-                eprintln!("Error verifying module: {}", err.to_string());
-                return;
-            }
+        // anything llvm doesn't like is a bug in here, but it still means there's no executable
+        if let Err(err) = self.module.verify() {
+            return Err(errors::Error::new(errors::ErrorKind::InvalidModule(err.to_string()), usize::MAX));
         }
 
         // compile to assembly
-        target_machine
+        self.target_machine
             .write_to_file(
                 &self.module,
                 FileType::Assembly,
@@ -108,28 +136,31 @@ impl<'a> LLVMGenerator<'a> {
 
         // Compile to an object file
         let obj_file = "output.o";
-        target_machine
+        self.target_machine
             .write_to_file(&self.module, FileType::Object, std::path::Path::new(obj_file))
             .expect("Failed to generate object file");
     
+        // the runtime gets compiled right along with the program
+        let runtime_file = "yeller_runtime.c";
+        std::fs::write(runtime_file, RUNTIME_SOURCE).expect("Failed to write runtime");
+
         // Use clang to link and create an executable
         let output = Command::new("clang")
-            .args(&[obj_file, "-o", output_file])
+            .args([obj_file, runtime_file, "-o", output_file])
             .output()
             .expect("Failed to run clang");
     
         // remove the object file
         std::fs::remove_file(obj_file).expect("Failed to remove object file");
+        std::fs::remove_file(runtime_file).expect("Failed to remove runtime");
     
         if !output.status.success() {
-            eprintln!(
-                "Error while linking with clang: {}",
-                String::from_utf8_lossy(&output.stderr)
-            );
-            return;
+            let message = String::from_utf8_lossy(&output.stderr).to_string();
+            return Err(errors::Error::new(errors::ErrorKind::LinkFailed(message), usize::MAX));
         }
     
         println!("Executable generated: ./{}", output_file);
+        Ok(())
     }
 
     fn ty_to_llvm_ty(&self, ty: &definition::Type) -> inkwell::types::BasicTypeEnum<'a> {
@@ -138,13 +169,48 @@ impl<'a> LLVMGenerator<'a> {
             /*definition::Type::I64 => self.context.i64_type().as_basic_type_enum(),
             definition::Type::U32 => self.context.i32_type().as_basic_type_enum(),
             definition::Type::U64 => self.context.i64_type().as_basic_type_enum(),
-            definition::Type::F64 => self.context.f64_type().as_basic_type_enum(),
-            definition::Type::Box(box inner_ty) |*/
+            definition::Type::F64 => self.context.f64_type().as_basic_type_enum(),*/
+            definition::Type::Pointer(box definition::Type::Function(params, ret_ty, variadic)) => {
+                self.fn_type(params, ret_ty, *variadic).ptr_type(inkwell::AddressSpace::from(0)).as_basic_type_enum()
+            }
+            definition::Type::Tuple(elements) => {
+                let elements = elements.iter().map(|ty| self.ty_to_llvm_ty(ty)).collect::<Vec<_>>();
+                self.context.struct_type(&elements, false).as_basic_type_enum()
+            }
+            definition::Type::Box(box inner_ty) |
             definition::Type::Pointer(box inner_ty) => {
                 let inner_ty = self.ty_to_llvm_ty(inner_ty);
                 inner_ty.ptr_type(inkwell::AddressSpace::from(0)).as_basic_type_enum()
             }
-            definition::Type::Function(_, _) => unreachable!(),
+            definition::Type::Function(..) | definition::Type::Void | definition::Type::Param(_) => unreachable!(),
+        }
+    }
+
+    fn fn_type(&self, params: &[definition::Type], ret_ty: &definition::Type, variadic: bool) -> inkwell::types::FunctionType<'a> {
+        let mut param_types = params.iter().map(|ty| self.get_metadata_type(ty)).collect::<Vec<_>>();
+        if self.uses_sret(ret_ty) {
+            param_types.insert(0, self.ty_to_llvm_ty(ret_ty).ptr_type(inkwell::AddressSpace::from(0)).into());
+            return self.context.void_type().fn_type(&param_types, variadic);
+        }
+        if *ret_ty == definition::Type::Void {
+            return self.context.void_type().fn_type(&param_types, variadic);
+        }
+        self.ty_to_llvm_ty(ret_ty).fn_type(&param_types, variadic)
+    }
+
+    fn uses_sret(&self, ret_ty: &definition::Type) -> bool {
+        matches!(ret_ty, definition::Type::Tuple(_)) && self.target_data.get_abi_size(&__ty_to_llvm_ty(self.context, ret_ty)) > MAX_DIRECT_RETURN_SIZE
+    }
+
+    fn sret_attribute(&self, ret_ty: &definition::Type) -> Attribute {
+        let kind = Attribute::get_named_enum_kind_id("sret");
+        self.context.create_type_attribute(kind, self.ty_to_llvm_ty(ret_ty).as_any_type_enum())
+    }
+
+    fn var_ty(&self, val: &definition::Val) -> definition::Type {
+        match val {
+            definition::Val::Var(name) => self.frontend_symbol_table.get(name).expect("Variable not found").ty.clone(),
+            definition::Val::Number(_) => definition::Type::I32,
         }
     }
 
@@ -156,14 +222,20 @@ impl<'a> LLVMGenerator<'a> {
             definition::Type::U64 => inkwell::types::BasicMetadataTypeEnum::IntType(self.context.i64_type()),
             definition::Type::F64 => inkwell::types::BasicMetadataTypeEnum::FloatType(self.context.f64_type()),*/
 
-            /*definition::Type::Box(box inner_ty) |*/
+            definition::Type::Pointer(box definition::Type::Function(params, ret_ty, variadic)) => {
+                inkwell::types::BasicMetadataTypeEnum::PointerType(self.fn_type(params, ret_ty, *variadic).ptr_type(inkwell::AddressSpace::from(0)))
+            }
+            definition::Type::Tuple(_) => {
+                inkwell::types::BasicMetadataTypeEnum::StructType(self.ty_to_llvm_ty(ty).into_struct_type())
+            }
+            definition::Type::Box(box inner_ty) |
             definition::Type::Pointer(box inner_ty) => {
                 // turn the inner_ty into a inkwell::types::PointerType
                 let inner_ty = self.ty_to_llvm_ty(inner_ty);
                 inkwell::types::BasicMetadataTypeEnum::PointerType(inner_ty.ptr_type(inkwell::AddressSpace::from(0)))
             }
 
-            definition::Type::Function(_, _) => unreachable!(),
+            definition::Type::Function(..) | definition::Type::Void | definition::Type::Param(_) => unreachable!(),
         }
     }
 
@@ -188,24 +260,7 @@ impl<'a> LLVMGenerator<'a> {
         var_collector.collect_function(&ir_function);
         let variables = var_collector.variables;
 
-        let (function, ret_ty) = match self.module.get_function(&ir_function.name) {
-            Some(f) => (f, f.get_type().get_return_type().unwrap()),
-            None => {
-                let param_types = ir_function.params.iter().map(|(_, ty)| ty).collect::<Vec<_>>();
-                let llvm_ret_type = self.ty_to_llvm_ty(&ir_function.return_type);
-
-                let linkage = if true { // check if global (automatically rn)
-                    inkwell::module::Linkage::External
-                } else {
-                    inkwell::module::Linkage::ExternalWeak
-                };
-
-                let param_types = param_types.iter().map(|ty| self.get_metadata_type(ty)).collect::<Vec<_>>();
-
-                let fn_type = llvm_ret_type.fn_type(&param_types, false);
-                (self.module.add_function(&ir_function.name, fn_type, Some(linkage)), llvm_ret_type)
-            }
-        };
+        let function = self.get_function(&ir_function.name);
 
         function.set_linkage(if true { // if global
             inkwell::module::Linkage::External
@@ -217,6 +272,11 @@ impl<'a> LLVMGenerator<'a> {
         let builder = self.context.create_builder();
         self.current_function = ir_function.name;
         self.symbol_table.clear();
+        self.sret = if self.uses_sret(&ir_function.return_type) {
+            Some(function.get_first_param().expect("uh oh").into_pointer_value())
+        } else {
+            None
+        };
 
         // self.label_table.clear();
         builder.position_at_end(entry);
@@ -229,9 +289,7 @@ impl<'a> LLVMGenerator<'a> {
             self.symbol_table.insert(variable, ptr_val);
         }
 
-        function.get_params().into_iter();
-
-        for (param, (name, _)) in function.get_params().into_iter().zip(ir_function.params.into_iter()) {
+        for (param, (name, _)) in function.get_params().into_iter().skip(self.sret.is_some() as usize).zip(ir_function.params.into_iter()) {
             let ptr_val = builder.build_alloca(param.get_type(), name.as_str()).expect("uh oh");
             builder.build_store(ptr_val, param).expect("uh oh");
             self.symbol_table.insert(name, ptr_val);
@@ -243,7 +301,10 @@ impl<'a> LLVMGenerator<'a> {
         }
 
         // return 0 (in case we end with a label)
-        builder.build_return(Some(&ret_ty.const_zero())).expect("uh oh");
+        match function.get_type().get_return_type() {
+            Some(ret_ty) => builder.build_return(Some(&ret_ty.const_zero())),
+            None => builder.build_return(None),
+        }.expect("uh oh");
 
         let fpm = PassManager::create(&self.module);
 
@@ -264,8 +325,19 @@ impl<'a> LLVMGenerator<'a> {
     fn generate_instruction(&mut self, builder: &inkwell::builder::Builder<'a>, instruction: definition::Instruction, ) {
         match instruction {
             definition::Instruction::Return(val) => {
-                let return_val = self.val_to_base(val, builder);
-                builder.build_return(Some(&return_val)).expect("uh oh");
+                let return_val = val.map(|val| self.val_to_base(val, builder));
+                match (self.sret, return_val) {
+                    (Some(sret), Some(return_val)) => {
+                        builder.build_store(sret, return_val).expect("uh oh");
+                        builder.build_return(None).expect("uh oh");
+                    }
+                    (None, Some(return_val)) => {
+                        builder.build_return(Some(&return_val)).expect("uh oh");
+                    }
+                    (_, None) => {
+                        builder.build_return(None).expect("uh oh");
+                    }
+                }
                 let temp_label = self.context.append_basic_block(self.module.get_function(&self.current_function).unwrap(), "after term");
                 builder.position_at_end(temp_label);
             }
@@ -325,8 +397,24 @@ impl<'a> LLVMGenerator<'a> {
                         };
                         builder.build_store(dest_val, result).expect("uh oh");
                     }
-                    definition::Binop::Equal => {
-                        let result = builder.build_int_compare(inkwell::IntPredicate::EQ, src1_val.into_int_value(), src2_val.into_int_value(), "equal").expect("uh oh");
+                    definition::Binop::Equal | definition::Binop::Less | definition::Binop::Greater => {
+                        // pointers are compared as addresses, numbers keep their sign
+                        let is_pointer = src1_val.is_pointer_value();
+                        let predicate = match (op, is_pointer) {
+                            (definition::Binop::Equal, _) => inkwell::IntPredicate::EQ,
+                            (definition::Binop::Less, true) => inkwell::IntPredicate::ULT,
+                            (definition::Binop::Less, false) => inkwell::IntPredicate::SLT,
+                            (_, true) => inkwell::IntPredicate::UGT,
+                            (_, false) => inkwell::IntPredicate::SGT,
+                        };
+                        let (src1_val, src2_val) = if is_pointer {
+                            let address_ty = self.context.ptr_sized_int_type(&self.target_data, None);
+                            (builder.build_ptr_to_int(src1_val.into_pointer_value(), address_ty, "address").expect("uh oh"),
+                             builder.build_ptr_to_int(src2_val.into_pointer_value(), address_ty, "address").expect("uh oh"))
+                        } else {
+                            (src1_val.into_int_value(), src2_val.into_int_value())
+                        };
+                        let result = builder.build_int_compare(predicate, src1_val, src2_val, "compare").expect("uh oh");
                         let result = builder.build_int_z_extend(result, self.context.i32_type(), "extend").expect("uh oh");
                         builder.build_store(dest_val, result).expect("uh oh");
                     }
@@ -358,7 +446,16 @@ impl<'a> LLVMGenerator<'a> {
                 // convert to i1
                 let val = builder.build_int_compare(inkwell::IntPredicate::NE, val.into_int_value(), self.context.i32_type().const_zero(), "compare").expect("uh oh");
                 let temp_label = self.context.append_basic_block(self.module.get_function(&self.current_function).unwrap(), "no branch");
-                builder.build_conditional_branch(val, temp_label, block).expect("uh oh");
+                builder.build_conditional_branch(val, block, temp_label).expect("uh oh");
+                builder.position_at_end(temp_label);
+            }
+            definition::Instruction::Switch { val, cases, default } => {
+                let val = self.val_to_base(val, builder);
+                let else_block = self.get_block(&default);
+                let i32_type = self.context.i32_type();
+                let cases = cases.iter().map(|(value, label)| (i32_type.const_int(*value, false), self.get_block(label))).collect::<Vec<_>>();
+                builder.build_switch(val.into_int_value(), else_block, &cases).expect("uh oh");
+                let temp_label = self.context.append_basic_block(self.module.get_function(&self.current_function).unwrap(), "after term");
                 builder.position_at_end(temp_label);
             }
             definition::Instruction::Label(label) => {
@@ -367,42 +464,106 @@ impl<'a> LLVMGenerator<'a> {
                     builder.build_unconditional_branch(block).expect("uh oh");
                 builder.position_at_end(block);
             }
+            definition::Instruction::Line(_) => {}
             definition::Instruction::FunctionCall(name, args, dst) => {
-                let function = match self.module.get_function(&name) {
-                    Some(f) => f,
-                    None => {
-                        let entry = self.frontend_symbol_table.get(&name).expect("Function not found");
-                        let (param_types, ret_type) = match &entry.ty {
-                            definition::Type::Function(params, ret) => (params, ret),
-                            _ => unreachable!("uh oh")
-                        };
-                        let linkage = if true { // is global
-                            inkwell::module::Linkage::External
-                        } else {
-                            inkwell::module::Linkage::ExternalWeak
-                        };
-
-                        let param_types = param_types.iter().map(|ty| self.get_metadata_type(ty)).collect::<Vec<_>>();
-
-                        let ret_type = self.ty_to_llvm_ty(&ret_type);
-
-                        let fn_type = ret_type.fn_type(&param_types, false);
-
-                        self.module.add_function(&name, fn_type, Some(linkage))
+                let function = self.get_function(&name);
+                // functions that return nothing have nowhere to put it
+                let ret_ty = dst.as_ref().map(|dst| self.var_ty(dst));
+                let dest_val = dst.map(|dst| self.get_ptr_from_val(dst));
+                let sret = ret_ty.as_ref().is_some_and(|ret_ty| self.uses_sret(ret_ty));
+                let mut arg_vals = Vec::new();
+                if let (true, Some(dest_val)) = (sret, dest_val) {
+                    arg_vals.push(dest_val.into());
+                }
+                for arg in args {
+                    arg_vals.push(self.val_to_base(arg, builder).into())
+                }
+                let result = builder.build_call(function, &arg_vals, "call").expect("uh oh");
+                if let (Some(ret_ty), Some(dest_val)) = (ret_ty, dest_val) {
+                    if sret {
+                        result.add_attribute(AttributeLoc::Param(0), self.sret_attribute(&ret_ty));
+                    } else {
+                        builder.build_store(dest_val, result.try_as_basic_value().left().unwrap()).expect("uh oh");
                     }
-                };
+                }
+            }
+            definition::Instruction::IndirectCall { ptr, args, dst, .. } => {
+                let ptr_val = self.val_to_base(ptr, builder).into_pointer_value();
+                let callable = inkwell::values::CallableValue::try_from(ptr_val).expect("uh oh");
+                // functions that return nothing have nowhere to put it
+                let ret_ty = dst.as_ref().map(|dst| self.var_ty(dst));
+                let dest_val = dst.map(|dst| self.get_ptr_from_val(dst));
+                let sret = ret_ty.as_ref().is_some_and(|ret_ty| self.uses_sret(ret_ty));
                 let mut arg_vals = Vec::new();
+                if let (true, Some(dest_val)) = (sret, dest_val) {
+                    arg_vals.push(dest_val.into());
+                }
                 for arg in args {
                     arg_vals.push(self.val_to_base(arg, builder).into())
                 }
+                let result = builder.build_call(callable, &arg_vals, "call").expect("uh oh");
+                if let (Some(ret_ty), Some(dest_val)) = (ret_ty, dest_val) {
+                    if sret {
+                        result.add_attribute(AttributeLoc::Param(0), self.sret_attribute(&ret_ty));
+                    } else {
+                        builder.build_store(dest_val, result.try_as_basic_value().left().unwrap()).expect("uh oh");
+                    }
+                }
+            }
+            definition::Instruction::MakeTuple { elements, dst } => {
                 let dest_val = self.get_ptr_from_val(dst);
-                let result = builder.build_call(function, &arg_vals, "call").expect("uh oh");
-                builder.build_store(dest_val, result.try_as_basic_value().left().unwrap()).expect("uh oh");
+                let tuple_ty = dest_val.get_type().get_element_type().into_struct_type();
+                let mut tuple = tuple_ty.get_undef();
+                for (index, element) in elements.into_iter().enumerate() {
+                    let element = self.val_to_base(element, builder);
+                    tuple = builder.build_insert_value(tuple, element, index as u32, "tuple").expect("uh oh").into_struct_value();
+                }
+                builder.build_store(dest_val, tuple).expect("uh oh");
+            }
+            definition::Instruction::TupleElement { src, index, dst } => {
+                let tuple = self.val_to_base(src, builder).into_struct_value();
+                let dest_val = self.get_ptr_from_val(dst);
+                let element = builder.build_extract_value(tuple, index, "element").expect("uh oh");
+                builder.build_store(dest_val, element).expect("uh oh");
+            }
+            definition::Instruction::Alloc(dst) => {
+                let dest_val = self.get_ptr_from_val(dst);
+                let box_ty = dest_val.get_type().get_element_type().into_pointer_type();
+                let size = box_ty.get_element_type().size_of().expect("uh oh");
+                let alloc = self.get_runtime_function("yeller_alloc");
+                let result = builder.build_call(alloc, &[size.into()], "alloc").expect("uh oh");
+                let result = builder.build_pointer_cast(result.try_as_basic_value().left().unwrap().into_pointer_value(), box_ty, "box").expect("uh oh");
+                builder.build_store(dest_val, result).expect("uh oh");
+            }
+            definition::Instruction::Trap(message) => {
+                let message = builder.build_global_string_ptr(&message, "trap message").expect("uh oh");
+                let trap = self.get_runtime_function("yeller_trap");
+                builder.build_call(trap, &[message.as_pointer_value().into()], "").expect("uh oh");
+                builder.build_unreachable().expect("uh oh");
+                let temp_label = self.context.append_basic_block(self.module.get_function(&self.current_function).unwrap(), "after trap");
+                builder.position_at_end(temp_label);
+            }
+            definition::Instruction::Free(val) => {
+                let ptr_val = self.val_to_base(val, builder).into_pointer_value();
+                let ptr_val = builder.build_pointer_cast(ptr_val, self.context.i8_type().ptr_type(inkwell::AddressSpace::from(0)), "unbox").expect("uh oh");
+                let free = self.get_runtime_function("yeller_free");
+                builder.build_call(free, &[ptr_val.into()], "").expect("uh oh");
+            }
+            definition::Instruction::Move { src, dst } => {
+                let src_ptr = self.get_ptr_from_val(src.clone());
+                let src_val = self.val_to_base(src, builder);
+                let dest_val = self.get_ptr_from_val(dst);
+                builder.build_store(dest_val, src_val).expect("uh oh");
+                builder.build_store(src_ptr, src_val.get_type().const_zero()).expect("uh oh");
             }
             definition::Instruction::GetAddress(src, dest, ..) => {
                 // get address of src and store it in dest
-                let ptr = *match src {
-                    definition::Val::Var(name) => self.symbol_table.get(&name).expect("Variable not found"),
+                let ptr = match src {
+                    definition::Val::Var(name) => match self.symbol_table.get(&name) {
+                        Some(ptr) => *ptr,
+                        // not a local, so it has to be a function
+                        None => self.get_function(&name).as_global_value().as_pointer_value(),
+                    },
                     definition::Val::Number(_) => panic!("uh oh")
                 };
                 let dest_val = self.get_ptr_from_val(dest);
@@ -421,6 +582,19 @@ impl<'a> LLVMGenerator<'a> {
                 let dest_ptr_val = self.val_to_base(dest_ptr, builder);
                 builder.build_store(dest_ptr_val.into_pointer_value(), src_val).expect("uh oh");
             }
+            definition::Instruction::PtrDiff { src1, src2, dst } => {
+                let src1_val = self.val_to_base(src1, builder).into_pointer_value();
+                let src2_val = self.val_to_base(src2, builder).into_pointer_value();
+                let dest_val = self.get_ptr_from_val(dst);
+                let result = builder.build_ptr_diff(src1_val, src2_val, "difference").expect("uh oh");
+                let result = builder.build_int_truncate(result, self.context.i32_type(), "difference").expect("uh oh");
+                builder.build_store(dest_val, result).expect("uh oh");
+            }
+            definition::Instruction::Null(dst) => {
+                let dest_val = self.get_ptr_from_val(dst);
+                let ptr_ty = dest_val.get_type().get_element_type().into_pointer_type();
+                builder.build_store(dest_val, ptr_ty.const_null()).expect("uh oh");
+            }
             definition::Instruction::AddPtr { ptr, index, dst } => {
                 let ptr_val = self.val_to_base(ptr.clone(), builder);
                 let index_val = self.val_to_base(index, builder);
@@ -436,6 +610,48 @@ impl<'a> LLVMGenerator<'a> {
         }
     }
 
+    fn get_function(&self, name: &str) -> inkwell::values::FunctionValue<'a> {
+        match self.module.get_function(name) {
+            Some(f) => f,
+            None => {
+                let entry = self.frontend_symbol_table.get(name).expect("Function not found");
+                let (param_types, ret_type, variadic) = match &entry.ty {
+                    definition::Type::Function(params, ret, variadic) => (params, ret, *variadic),
+                    _ => unreachable!("uh oh")
+                };
+                let linkage = if true { // is global
+                    inkwell::module::Linkage::External
+                } else {
+                    inkwell::module::Linkage::ExternalWeak
+                };
+
+                let fn_type = self.fn_type(param_types, ret_type, variadic);
+
+                let function = self.module.add_function(name, fn_type, Some(linkage));
+                if self.uses_sret(ret_type) {
+                    function.add_attribute(AttributeLoc::Param(0), self.sret_attribute(ret_type));
+                }
+                function
+            }
+        }
+    }
+
+    fn get_runtime_function(&self, name: &str) -> inkwell::values::FunctionValue<'a> {
+        if let Some(f) = self.module.get_function(name) {
+            return f;
+        }
+
+        let byte_ptr = self.context.i8_type().ptr_type(inkwell::AddressSpace::from(0));
+        let fn_type = match name {
+            "yeller_alloc" => byte_ptr.fn_type(&[self.context.i64_type().into()], false),
+            "yeller_free" => self.context.void_type().fn_type(&[byte_ptr.into()], false),
+            "yeller_trap" => self.context.void_type().fn_type(&[byte_ptr.into()], false),
+            _ => unreachable!("uh oh"),
+        };
+
+        self.module.add_function(name, fn_type, Some(inkwell::module::Linkage::External))
+    }
+
     fn get_block(&mut self, label: &String) -> inkwell::basic_block::BasicBlock<'a> {
         if let Some(block) = self.label_table.get(label) {
             *block
@@ -464,4 +680,21 @@ impl<'a> LLVMGenerator<'a> {
             }
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_type_sizes() {
+        // the tests run on the machine they compile for, which is 64 bit
+        let sizes = TypeSizes::new();
+        let int = definition::Type::I32;
+        let pointer = definition::Type::Pointer(Box::new(definition::Type::I32));
+        assert_eq!(sizes.size_of(&int), 4);
+        assert_eq!(sizes.size_of(&pointer), 8);
+        // the int gets padded out so the pointer lines up
+        assert_eq!(sizes.size_of(&definition::Type::Tuple(vec![int.clone(), pointer.clone()])), 16);
+        assert_eq!(sizes.size_of(&definition::Type::Tuple(vec![int.clone(), int.clone(), int])), 12);
+    }
+}
@@ -22,7 +22,9 @@ impl<'a> Collector<'a> {
     fn collect_instruction(&mut self, instruction: &definition::Instruction) {
         match instruction {
             definition::Instruction::Return(val) => {
-                self.collect_val(val);
+                if let Some(val) = val {
+                    self.collect_val(val);
+                }
             }
             definition::Instruction::Binary { src1, src2, dst, .. } => {
                 self.collect_val(src1);
@@ -39,11 +41,25 @@ impl<'a> Collector<'a> {
             definition::Instruction::JumpIfNotZero(val, ..) => {
                 self.collect_val(val);
             }
+            definition::Instruction::Switch { val, .. } => {
+                self.collect_val(val);
+            }
             definition::Instruction::FunctionCall(_, args, dst) => {
                 for arg in args {
                     self.collect_val(arg);
                 }
-                self.collect_val(dst);
+                if let Some(dst) = dst {
+                    self.collect_val(dst);
+                }
+            }
+            definition::Instruction::IndirectCall { ptr, args, dst, .. } => {
+                self.collect_val(ptr);
+                for arg in args {
+                    self.collect_val(arg);
+                }
+                if let Some(dst) = dst {
+                    self.collect_val(dst);
+                }
             }
             definition::Instruction::GetAddress(_, val) => {
                 self.collect_val(val);
@@ -61,8 +77,34 @@ impl<'a> Collector<'a> {
                 self.collect_val(index);
                 self.collect_val(dst);
             }
+            definition::Instruction::MakeTuple { elements, dst } => {
+                for element in elements {
+                    self.collect_val(element);
+                }
+                self.collect_val(dst);
+            }
+            definition::Instruction::TupleElement { src, dst, .. } => {
+                self.collect_val(src);
+                self.collect_val(dst);
+            }
+            definition::Instruction::PtrDiff { src1, src2, dst } => {
+                self.collect_val(src1);
+                self.collect_val(src2);
+                self.collect_val(dst);
+            }
+            definition::Instruction::Null(val) |
+            definition::Instruction::Alloc(val) |
+            definition::Instruction::Free(val) => {
+                self.collect_val(val);
+            }
+            definition::Instruction::Move { src, dst } => {
+                self.collect_val(src);
+                self.collect_val(dst);
+            }
             definition::Instruction::Jump(_) |
-            definition::Instruction::Label(_) => {}
+            definition::Instruction::Label(_) |
+            definition::Instruction::Line(_) |
+            definition::Instruction::Trap(_) => {}
         }
     }
 
@@ -2,7 +2,6 @@
 
 extern crate rand;
 extern crate inkwell;
-use rand::Rng;
 
 mod formatting;
 mod lexer;
@@ -10,47 +9,142 @@ mod parser;
 mod semantic_analysis;
 mod ir;
 mod llvm_gen;
+mod modules;
+mod rules;
 
 mod errors;
+mod warnings;
 
-fn compile(input: &str, output_file: &str) -> Result<(), errors::Error> {
-    // 1/5 chance to fail
-    if rand::rng().random_range(0..5) == 0 {
-        return Err(errors::Error::new(errors::ErrorKind::RandomChance, usize::MAX));
-    }
+// everything that can be changed from the command line
+#[derive(Default)]
+pub struct Options {
+    // leaves out every "i insist" check
+    pub release: bool,
+    // a variable can't share its name with anything it can see
+    pub no_shadowing: bool,
+    // which warnings get ignored and which ones stop the compile
+    pub lints: warnings::LintLevels,
+    // the house rules and how strict each one is, from yeller.toml
+    pub rules: rules::RuleSet,
+    // prints who calls who
+    pub report_callgraph: bool,
+}
 
-    formatting::formatting_check(input)?;
+fn compile(input_file: &str, output_file: &str, options: &Options) -> Result<(), errors::Error> {
+    let mut warnings = Vec::new();
+    options.rules.check(&rules::Subject::Compile, usize::MAX, &mut warnings)?;
 
-    let mut parser = parser::Parser::new(input)?;
-    let program = parser.parse_program()?;
+    let (program, loader_warnings) = modules::ModuleLoader::new(&options.rules).load_program(input_file)?;
+    warnings.extend(loader_warnings);
 
-    let (program, symbol_table) = semantic_analysis::analyze(program)?;
+    let (program, symbol_table, analysis_warnings) = semantic_analysis::analyze(program, options)?;
+    warnings.extend(analysis_warnings);
 
     //println!("{:#?}", program);
 
-    let mut ir_generator = ir::IRGenerator::new(symbol_table);
+    let mut ir_generator = ir::IRGenerator::new(symbol_table, options.release);
     let program = ir_generator.generate_ir(program)?;
+    ir::definite_assignment::DefiniteAssignmentChecker::new().check_program(&program)?;
+    let mut liveness_checker = ir::liveness::LivenessChecker::new();
+    liveness_checker.check_program(&program);
+    warnings.extend(liveness_checker.warnings);
+
+    report_warnings(warnings, options, input_file)?;
 
     //println!("{:#?}", program);
 
     let context = llvm_gen::LLVMGenerator::create_context();
     let llvm_gen = llvm_gen::LLVMGenerator::new(&context, &ir_generator.symbol_table);
-    llvm_gen.generate(program, output_file);
+    llvm_gen.generate(program, output_file)
+}
+
+// prints every warning that's still allowed to be one. the first denied one fails the compile instead
+fn report_warnings(warnings: Vec<warnings::Warning>, options: &Options, input_file: &str) -> Result<(), errors::Error> {
+    let mut denied = None;
+    for warning in warnings {
+        match options.lints.level(warning.kind.lint()) {
+            warnings::Severity::Allow => {}
+            warnings::Severity::Warn => warning.report(&read_source(&warning.file, input_file)),
+            warnings::Severity::Deny => {
+                denied.get_or_insert(warning);
+            }
+        }
+    }
+
+    match denied {
+        Some(warning) => Err(warning.into_error()),
+        None => Ok(()),
+    }
+}
 
-    Ok(())
+// the text of whichever file the problem is in
+fn read_source(file: &Option<String>, input_file: &str) -> String {
+    let file = file.clone().unwrap_or_else(|| input_file.to_string());
+    std::fs::read_to_string(&file).unwrap_or_default()
 }
 
 fn main() {
     // read args
     let args: Vec<String> = std::env::args().collect();
-    if args.len() != 3 {
-        eprintln!("Usage: {} <input> <output>", args[0]);
+    let (flags, files): (Vec<&String>, Vec<&String>) = args[1..].iter().partition(|arg| arg.starts_with("--"));
+    let mut options = Options::default();
+    let mut config = None;
+    for flag in flags {
+        let (name, value) = match flag.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (flag.as_str(), None),
+        };
+
+        match (name, value) {
+            ("--release", None) => options.release = true,
+            ("--no-shadowing", None) => options.no_shadowing = true,
+            ("--config", Some(file)) => config = Some(file.to_string()),
+            ("--report", Some("callgraph")) => options.report_callgraph = true,
+            ("--report", Some(report)) => {
+                eprintln!("There's no report called {}. All I've got is callgraph.", report);
+                std::process::exit(1);
+            }
+            ("--allow", Some(lint)) | ("--deny", Some(lint)) => {
+                let severity = if name == "--deny" { warnings::Severity::Deny } else { warnings::Severity::Allow };
+                if !options.lints.set(lint, severity) {
+                    eprintln!("There's no such thing as {}. The ones I know about are {}.", lint, warnings::LINTS.join(", "));
+                    std::process::exit(1);
+                }
+            }
+            _ => {
+                eprintln!("What is {}? I don't take that.", flag);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if files.len() != 2 {
+        eprintln!("Usage: {} [--release] [--no-shadowing] [--allow=<lint>] [--deny=<lint>] [--config=<file>] [--report=callgraph] <input> <output>", args[0]);
         std::process::exit(1);
     }
 
-    let input = std::fs::read_to_string(&args[1]).expect("Failed to read input file");
-    match compile(&input, &args[2]) {
+    // a yeller.toml where the compiler is run from gets used without asking
+    let config = match config {
+        Some(file) => Some(file),
+        None if std::path::Path::new("yeller.toml").exists() => Some("yeller.toml".to_string()),
+        None => None,
+    };
+    if let Some(file) = config {
+        let text = match std::fs::read_to_string(&file) {
+            Ok(text) => text,
+            Err(_) => {
+                eprintln!("I can't read {}. Did you make it up?", file);
+                std::process::exit(1);
+            }
+        };
+        if let Err(e) = options.rules.configure(&text) {
+            e.report(&text);
+            std::process::exit(1);
+        }
+    }
+
+    match compile(files[0], files[1], &options) {
         Ok(_) => println!("Compilation successful"),
-        Err(e) => e.report(&input),
+        Err(e) => e.report(&read_source(&e.file, files[0])),
     }
 }
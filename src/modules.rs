@@ -0,0 +1,241 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use crate::parser::{self, nodes};
+use crate::formatting;
+use crate::errors;
+use crate::rules;
+use crate::warnings;
+
+// loads the file being compiled plus everything it imports and glues it all into one program.
+// functions in imported modules get called module.name so they can't clash with anyone else's.
+pub struct ModuleLoader<'a> {
+    loaded: HashSet<String>,
+    functions: Vec<nodes::FunctionDefinition>,
+    rules: &'a rules::RuleSet,
+    warnings: Vec<warnings::Warning>,
+}
+
+impl<'a> ModuleLoader<'a> {
+    pub fn new(rules: &'a rules::RuleSet) -> Self {
+        Self {
+            loaded: HashSet::new(),
+            functions: Vec::new(),
+            rules,
+            warnings: Vec::new(),
+        }
+    }
+
+    // gives back the whole program and whatever the house rules had to say about how it was written
+    pub fn load_program(mut self, input_file: &str) -> Result<(nodes::Program, Vec<warnings::Warning>), errors::Error> {
+        let input = std::fs::read_to_string(input_file)
+            .map_err(|_| errors::Error::new(errors::ErrorKind::ModuleNotFound(input_file.to_string()), usize::MAX))?;
+        let path = Path::new(input_file);
+        if let Some(stem) = path.file_stem() {
+            self.loaded.insert(stem.to_string_lossy().to_string());
+        }
+        self.load_module(path, &input, None)?;
+
+        Ok((nodes::Program { imports: Vec::new(), functions: self.functions }, self.warnings))
+    }
+
+    // `module` is None for the file being compiled, its functions keep their names
+    fn load_module(&mut self, path: &Path, input: &str, module: Option<&str>) -> Result<(), errors::Error> {
+        let file = module.map(|_| path.display().to_string());
+
+        // every file gets its own formatting check and its own function numbering
+        let mut file_warnings = Vec::new();
+        formatting::formatting_check(input, self.rules, &mut file_warnings).map_err(|e| e.in_file(file.clone()))?;
+        let mut parser = parser::Parser::new(input, self.rules).map_err(|e| e.in_file(file.clone()))?;
+        let program = parser.parse_program().map_err(|e| e.in_file(file.clone()))?;
+        file_warnings.extend(parser.warnings);
+        self.warnings.extend(file_warnings.into_iter().map(|warning| warning.in_file(file.clone())));
+
+        let imported = program.imports.iter().map(|import| import.module.clone()).collect::<HashSet<_>>();
+        let own_functions = program.functions.iter().filter(|function| function.body.is_some()).map(|function| function.name.clone()).collect::<HashSet<_>>();
+
+        for mut function in program.functions {
+            if let Some(body) = &mut function.body {
+                let mut scopes = vec![function.params.iter().map(|(name, _)| name.clone()).collect::<HashSet<_>>()];
+                visit_block(body, &mut scopes, &mut |name, local, line| {
+                    if let Some((other, _)) = name.split_once('.') {
+                        if !imported.contains(other) {
+                            return Err(errors::Error::new(errors::ErrorKind::ModuleNotImported(other.to_string()), line));
+                        }
+                    } else if let Some(module) = module {
+                        // a local with the same name as one of the module's functions hides it
+                        if own_functions.contains(name.as_str()) && !local {
+                            *name = format!("{}.{}", module, name);
+                        }
+                    }
+                    Ok(())
+                }).map_err(|e| e.in_file(file.clone()))?;
+
+                // extern declarations keep their C names
+                if let Some(module) = module {
+                    function.name = format!("{}.{}", module, function.name);
+                }
+            } else if let Some(existing) = self.functions.iter().find(|existing| existing.name == function.name) {
+                // the same C function declared in more than one file only gets declared once
+                let same_params = existing.params.iter().map(|(_, ty)| ty).eq(function.params.iter().map(|(_, ty)| ty));
+                if !same_params || existing.return_type != function.return_type || existing.variadic != function.variadic {
+                    return Err(errors::Error::new(errors::ErrorKind::ExternMismatch(function.name), function.line_started).in_file(file));
+                }
+                continue;
+            }
+
+            function.file = file.clone();
+            self.functions.push(function);
+        }
+
+        let directory = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        for import in program.imports {
+            if !self.loaded.insert(import.module.clone()) {
+                continue;
+            }
+
+            let import_path: PathBuf = directory.join(format!("{}.yl", import.module));
+            let import_input = std::fs::read_to_string(&import_path)
+                .map_err(|_| errors::Error::new(errors::ErrorKind::ModuleNotFound(import.module.clone()), import.line_started).in_file(file.clone()))?;
+            self.load_module(&import_path, &import_input, Some(&import.module))?;
+        }
+
+        Ok(())
+    }
+}
+
+// calls `f` with every function or variable name used in the block, and whether it's a local that can
+// be seen from there. `scopes` holds what's been declared so far in each block we're inside
+fn visit_block<F>(block: &mut nodes::Block, scopes: &mut Vec<HashSet<String>>, f: &mut F) -> Result<(), errors::Error>
+where F: FnMut(&mut String, bool, usize) -> Result<(), errors::Error> {
+    scopes.push(HashSet::new());
+
+    for item in &mut block.items {
+        match item {
+            nodes::BlockItem::Statement(statement) => visit_statement(statement, scopes, f)?,
+            // the new name only counts once its value has been worked out
+            nodes::BlockItem::Declaration(declaration) => {
+                visit_expression(&mut declaration.value, scopes, f)?;
+                scopes.last_mut().unwrap().insert(declaration.name.clone());
+            }
+            nodes::BlockItem::Destructure(destructure) => {
+                visit_expression(&mut destructure.value, scopes, f)?;
+                scopes.last_mut().unwrap().extend(destructure.names.iter().map(|(name, _)| name.clone()));
+            }
+        }
+    }
+
+    scopes.pop();
+    Ok(())
+}
+
+fn visit_statement<F>(statement: &mut nodes::Statement, scopes: &mut Vec<HashSet<String>>, f: &mut F) -> Result<(), errors::Error>
+where F: FnMut(&mut String, bool, usize) -> Result<(), errors::Error> {
+    match &mut statement.kind {
+        nodes::StatementKind::Return(None) => {}
+        nodes::StatementKind::Return(Some(expression)) | nodes::StatementKind::Expression(expression) | nodes::StatementKind::StaticAssert(expression)
+            | nodes::StatementKind::Assert(expression, _) => {
+            visit_expression(expression, scopes, f)?;
+        }
+        nodes::StatementKind::Block(block) => visit_block(block, scopes, f)?,
+        nodes::StatementKind::Defer(body) => visit_statement(body, scopes, f)?,
+        nodes::StatementKind::If(branches, else_block) => {
+            for (condition, block) in branches {
+                visit_expression(condition, scopes, f)?;
+                visit_statement(block, scopes, f)?;
+            }
+            if let Some(block) = else_block {
+                visit_statement(block, scopes, f)?;
+            }
+        }
+        nodes::StatementKind::Switch(value, cases, default) => {
+            visit_expression(value, scopes, f)?;
+            for case in cases {
+                visit_expression(&mut case.low, scopes, f)?;
+                visit_expression(&mut case.high, scopes, f)?;
+                visit_statement(&mut case.body, scopes, f)?;
+            }
+            if let Some(block) = default {
+                visit_statement(block, scopes, f)?;
+            }
+        }
+        nodes::StatementKind::While(condition, block) | nodes::StatementKind::DoWhile(block, condition) => {
+            visit_expression(condition, scopes, f)?;
+            visit_statement(block, scopes, f)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn visit_expression<F>(expression: &mut nodes::Expression, scopes: &[HashSet<String>], f: &mut F) -> Result<(), errors::Error>
+where F: FnMut(&mut String, bool, usize) -> Result<(), errors::Error> {
+    let line = expression.line_started;
+    match &mut expression.kind {
+        nodes::ExpressionKind::Number(_) | nodes::ExpressionKind::Null | nodes::ExpressionKind::SizeOf(_) => {}
+        nodes::ExpressionKind::Variable(name) => {
+            let local = is_local(scopes, name);
+            f(name, local, line)?
+        }
+        nodes::ExpressionKind::FunctionCall(name, args) => {
+            let local = is_local(scopes, name);
+            f(name, local, line)?;
+            for arg in args {
+                visit_expression(arg, scopes, f)?;
+            }
+        }
+        nodes::ExpressionKind::IndirectCall(callee, args) => {
+            visit_expression(callee, scopes, f)?;
+            for arg in args {
+                visit_expression(arg, scopes, f)?;
+            }
+        }
+        nodes::ExpressionKind::Tuple(elements) => {
+            for element in elements {
+                visit_expression(element, scopes, f)?;
+            }
+        }
+        nodes::ExpressionKind::Binary(_, left, right) | nodes::ExpressionKind::Assign(left, right) | nodes::ExpressionKind::Subscript(left, right) => {
+            visit_expression(left, scopes, f)?;
+            visit_expression(right, scopes, f)?;
+        }
+        nodes::ExpressionKind::IsZero(inner) | nodes::ExpressionKind::RequestRoom(inner) | nodes::ExpressionKind::Move(inner)
+            | nodes::ExpressionKind::AddressOf(inner) | nodes::ExpressionKind::Dereference(inner) => {
+            visit_expression(inner, scopes, f)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn is_local(scopes: &[HashSet<String>], name: &str) -> bool {
+    scopes.iter().any(|scope| scope.contains(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_hides_module_function() {
+        let rules = rules::RuleSet::default();
+        let (program, _) = ModuleLoader::new(&rules).load_program("module_examples/shadowed.yl").unwrap();
+        let function = program.functions.iter().find(|function| function.name == "helpr.dubl").unwrap();
+
+        // the call still goes to the module's add, the local that shares its name stays as it is
+        let mut names = Vec::new();
+        visit_block(&mut function.body.clone().unwrap(), &mut Vec::new(), &mut |name, _, _| {
+            names.push(name.clone());
+            Ok(())
+        }).unwrap();
+        assert_eq!(names, vec!["helpr.add", "number", "number", "add"]);
+    }
+
+    #[test]
+    fn test_extern_declared_twice() {
+        // main and the module it imports both declare printf for themselves
+        let rules = rules::RuleSet::default();
+        let (program, _) = ModuleLoader::new(&rules).load_program("module_examples/own_extern.yl").unwrap();
+        assert_eq!(program.functions.iter().filter(|function| function.name == "printf").count(), 1);
+        assert!(program.functions.iter().any(|function| function.name == "prnt.show"));
+    }
+}
@@ -1,5 +1,7 @@
 use crate::lexer::{Token, TokenKind, Keyword, Lexer};
 use crate::errors;
+use crate::rules;
+use crate::warnings;
 
 pub mod nodes;
 
@@ -7,21 +9,72 @@ pub struct Parser<'a> {
     lexer: Lexer<'a>,
     current_token: Token,
     function_counter: u64,
+    // how many things the function being parsed does, and how many of them it asked for nicely
+    items_this_function: u32,
+    polite_this_function: u32,
+    // the type parameters of the function being parsed. None while its return type is being read,
+    // since they're only written after it
+    type_params: Option<Vec<String>>,
+    rules: &'a rules::RuleSet,
+    pub warnings: Vec<warnings::Warning>,
 }
 
 impl<'a> Parser<'a> {
-    pub fn new(input: &'a str) -> Result<Self, errors::Error> {
+    pub fn new(input: &'a str, rules: &'a rules::RuleSet) -> Result<Self, errors::Error> {
         let mut lexer = Lexer::new(input);
         let current_token = lexer.next_token()?;
-        Ok(Self { lexer, current_token, function_counter: 1 })
+        Ok(Self { lexer, current_token, function_counter: 1, items_this_function: 0, polite_this_function: 0, type_params: None, rules, warnings: Vec::new() })
     }
 
     pub fn parse_program(&mut self) -> Result<nodes::Program, errors::Error> {
+        let mut imports = Vec::new();
         let mut functions = Vec::new();
         while self.current_token.kind != TokenKind::EOF {
-            functions.push(self.parse_function_definition()?);
+            if self.current_token.kind == TokenKind::Keyword(Keyword::I) && self.peek()?.kind == TokenKind::Keyword(Keyword::Am) {
+                imports.push(self.parse_import()?);
+            } else {
+                functions.push(self.parse_function_definition()?);
+            }
+        }
+        Ok(nodes::Program { imports, functions })
+    }
+
+    fn parse_import(&mut self) -> Result<nodes::Import, errors::Error> {
+        // i am importing the module named {name} period
+        let line_started = self.current_token.line;
+        self.expect_keyword(Keyword::I)?;
+        self.expect_keyword(Keyword::Am)?;
+        self.expect_word("importing")?;
+        self.expect_keyword(Keyword::The)?;
+        self.expect_word("module")?;
+        self.expect_keyword(Keyword::Named)?;
+        let module = self.parse_identifier()?;
+        self.expect_keyword(Keyword::Period)?;
+        Ok(nodes::Import { module, line_started })
+    }
+
+    fn parse_identifier(&mut self) -> Result<String, errors::Error> {
+        if let TokenKind::Identifier(name) = &self.current_token.kind {
+            let name = name.clone();
+            self.next()?;
+            Ok(name)
+        } else {
+            Err(errors::Error::new(errors::ErrorKind::UnexpectedToken {
+                expected: "an identifier".to_string(),
+                found: self.current_token.kind.to_string(),
+            }, self.current_token.line))
+        }
+    }
+
+    fn parse_qualifier(&mut self, name: String) -> Result<String, errors::Error> {
+        // {name} from {module}, which is stored as module.name since a '.' can't show up in source
+        if self.at_word("from") {
+            self.next()?;
+            let module = self.parse_identifier()?;
+            Ok(format!("{}.{}", module, name))
+        } else {
+            Ok(name)
         }
-        Ok(nodes::Program { functions })
     }
 
     fn next(&mut self) -> Result<(), errors::Error> {
@@ -53,17 +106,94 @@ impl<'a> Parser<'a> {
         self.expect(TokenKind::Keyword(kind))
     }
 
+    // words that only mean something in one spot are left as identifiers by the lexer, so
+    // everywhere else they can still be used as names
+    fn at_word(&self, word: &str) -> bool {
+        matches!(&self.current_token.kind, TokenKind::Identifier(name) if name == word)
+    }
+
+    fn peek_word(&self, word: &str) -> Result<bool, errors::Error> {
+        Ok(matches!(self.peek()?.kind, TokenKind::Identifier(ref name) if name == word))
+    }
+
+    // please followed by something you can do to a value is a variable called please, not manners
+    fn at_please(&self) -> Result<bool, errors::Error> {
+        if !self.at_word("please") {
+            return Ok(false);
+        }
+        let next = self.peek()?.kind;
+        Ok(self.get_prec(&next) < 0 && !matches!(next, TokenKind::Keyword(Keyword::Period) | TokenKind::Keyword(Keyword::Is)))
+    }
+
+    fn expect_word(&mut self, word: &str) -> Result<(), errors::Error> {
+        self.expect(TokenKind::Identifier(word.to_string()))
+    }
+
     fn parse_type(&mut self) -> Result<nodes::Type, errors::Error> {
         match self.current_token.kind {
             TokenKind::Keyword(Keyword::I32) => {
                 self.next()?;
                 Ok(nodes::Type::I32)
             }
+            TokenKind::Identifier(_) if self.at_word("nothing") => {
+                self.next()?;
+                Ok(nodes::Type::Void)
+            }
             TokenKind::Keyword(Keyword::Pointing) => {
                 self.next()?;
                 self.expect_keyword(Keyword::At)?;
                 Ok(nodes::Type::Pointer(Box::new(self.parse_type()?)))
             }
+            TokenKind::Identifier(_) if self.at_word("box") => {
+                self.next()?;
+                self.expect_keyword(Keyword::Of)?;
+                Ok(nodes::Type::Box(Box::new(self.parse_type()?)))
+            }
+            TokenKind::Identifier(_) if self.at_word("tuple") && self.peek()?.kind == TokenKind::Keyword(Keyword::Of) => {
+                // tuple of left_brace T comma T right_brace
+                self.next()?;
+                self.expect_keyword(Keyword::Of)?;
+                self.expect(TokenKind::LBrace)?;
+                let mut elements = vec![self.parse_type()?];
+                while self.current_token.kind == TokenKind::Comma {
+                    self.next()?;
+                    elements.push(self.parse_type()?);
+                }
+                self.expect(TokenKind::RBrace)?;
+                Ok(nodes::Type::Tuple(elements))
+            }
+            TokenKind::Keyword(Keyword::Fn) => {
+                // function that shall take the parameters left_brace T comma T right_brace and return T
+                self.next()?;
+                self.expect_keyword(Keyword::That)?;
+                self.expect_keyword(Keyword::Shall)?;
+                self.expect_keyword(Keyword::Take)?;
+                self.expect_keyword(Keyword::The)?;
+                self.expect_keyword(Keyword::Parameters)?;
+                self.expect(TokenKind::LBrace)?;
+                let mut params = Vec::new();
+                if self.current_token.kind != TokenKind::RBrace && self.current_token.kind != TokenKind::Keyword(Keyword::And) {
+                    params.push(self.parse_type()?);
+                    while self.current_token.kind == TokenKind::Comma {
+                        self.next()?;
+                        params.push(self.parse_type()?);
+                    }
+                }
+                let variadic = self.parse_variadic_marker()?;
+                self.expect(TokenKind::RBrace)?;
+                self.expect_keyword(Keyword::And)?;
+                self.expect_keyword(Keyword::Return)?;
+                Ok(nodes::Type::Function(params, Box::new(self.parse_type()?), variadic))
+            }
+            TokenKind::Identifier(ref name) => {
+                // anything that isn't a built in type has to be one of the function's type parameters
+                let name = name.clone();
+                if self.type_params.as_ref().is_some_and(|type_params| !type_params.contains(&name)) {
+                    return Err(errors::Error::new(errors::ErrorKind::UnknownType(name), self.current_token.line));
+                }
+                self.next()?;
+                Ok(nodes::Type::Param(name))
+            }
             _ => Err(errors::Error::new(errors::ErrorKind::UnexpectedToken {
                 expected: "a type".to_string(),
                 found: self.current_token.kind.to_string(),
@@ -85,6 +215,53 @@ impl<'a> Parser<'a> {
         Ok((name, self.parse_type()?))
     }
 
+    fn parse_variadic_marker(&mut self) -> Result<bool, errors::Error> {
+        // ... and then some more
+        if self.current_token.kind != TokenKind::Keyword(Keyword::And) {
+            return Ok(false);
+        }
+        self.next()?;
+        self.expect_word("then")?;
+        self.expect_word("some")?;
+        self.expect_word("more")?;
+        Ok(true)
+    }
+
+    // the return type gets read before we know what the type parameters are, so it's checked afterwards
+    fn check_type_params(&self, ty: &nodes::Type, line: usize) -> Result<(), errors::Error> {
+        match ty {
+            nodes::Type::I32 | nodes::Type::Void => Ok(()),
+            nodes::Type::Pointer(inner) | nodes::Type::Box(inner) => self.check_type_params(inner, line),
+            nodes::Type::Tuple(elements) => elements.iter().try_for_each(|element| self.check_type_params(element, line)),
+            nodes::Type::Function(params, return_type, _) => {
+                params.iter().try_for_each(|param| self.check_type_params(param, line))?;
+                self.check_type_params(return_type, line)
+            }
+            nodes::Type::Param(name) => match &self.type_params {
+                Some(type_params) if !type_params.contains(name) => Err(errors::Error::new(errors::ErrorKind::UnknownType(name.clone()), line)),
+                _ => Ok(()),
+            },
+        }
+    }
+
+    fn parse_type_params(&mut self) -> Result<Vec<String>, errors::Error> {
+        // ... for any type named {name} comma {name}
+        let mut type_params = Vec::new();
+        if !self.at_word("for") {
+            return Ok(type_params);
+        }
+        self.next()?;
+        self.expect_word("any")?;
+        self.expect_word("type")?;
+        self.expect_keyword(Keyword::Named)?;
+        type_params.push(self.parse_identifier()?);
+        while self.current_token.kind == TokenKind::Comma {
+            self.next()?;
+            type_params.push(self.parse_identifier()?);
+        }
+        Ok(type_params)
+    }
+
     fn parse_function_definition(&mut self) -> Result<nodes::FunctionDefinition, errors::Error> {
         let line_started = self.current_token.line;
         self.expect_keyword(Keyword::The)?;
@@ -101,16 +278,13 @@ impl<'a> Parser<'a> {
         };
         self.next()?;
 
-        if num != self.function_counter {
-            return Err(errors::Error::new(errors::ErrorKind::WrongfunctionCount {
-                expected: self.function_counter,
-                found: num,
-            }, self.current_token.line));
-        }
+        self.rules.check(&rules::Subject::FunctionNumber { expected: self.function_counter, found: num }, self.current_token.line, &mut self.warnings)?;
 
         self.function_counter += 1;
 
         self.expect_keyword(Keyword::Is)?;
+        self.type_params = None;
+        let return_line = self.current_token.line;
         let return_type = self.parse_type()?;
         self.expect_keyword(Keyword::Shall)?;
         self.expect_keyword(Keyword::Be)?;
@@ -125,27 +299,47 @@ impl<'a> Parser<'a> {
             }, self.current_token.line));
         };
         self.next()?;
+        let type_params = self.parse_type_params()?;
+        self.type_params = Some(type_params.clone());
+        self.check_type_params(&return_type, return_line)?;
         self.expect(TokenKind::LBracket)?;
         let mut params = Vec::new();
-        if self.current_token.kind != TokenKind::RBracket {
+        if self.current_token.kind != TokenKind::RBracket && self.current_token.kind != TokenKind::Keyword(Keyword::And) {
             params.push(self.parse_param()?);
             while self.current_token.kind == TokenKind::Comma {
                 self.next()?;
                 params.push(self.parse_param()?);
             }
         }
+        let variadic = self.parse_variadic_marker()?;
         self.expect(TokenKind::RBracket)?;
 
+        self.items_this_function = 0;
+        self.polite_this_function = 0;
         let body = if self.current_token.kind == TokenKind::Semicolon {
             self.next()?;
             None
         } else { Some(self.parse_block()?) };
+
+        if body.is_some() {
+            let politeness = rules::Subject::Politeness { polite: self.polite_this_function, items: self.items_this_function };
+            self.rules.check(&politeness, line_started, &mut self.warnings)?;
+        }
+
+        // we can declare c's varargs functions but we have no way to read the extra args ourselves
+        if variadic && body.is_some() {
+            return Err(errors::Error::new(errors::ErrorKind::VariadicDefinition(name), line_started));
+        }
+
         Ok(nodes::FunctionDefinition {
             name,
+            type_params,
             params,
+            variadic,
             return_type,
             body,
             line_started,
+            file: None,
         })
     }
 
@@ -161,25 +355,56 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_block_item(&mut self) -> Result<nodes::BlockItem, errors::Error> {
+        // anything in a block can start with please. it doesn't change what it does, but it's nice
+        self.items_this_function += 1;
+        if self.at_please()? {
+            self.polite_this_function += 1;
+            self.next()?;
+        }
+
         match self.current_token.kind {
             TokenKind::Keyword(Keyword::I) => {
                 if self.peek()?.kind == TokenKind::Keyword(Keyword::Am) {
-                    self.parse_declaration().map(nodes::BlockItem::Declaration)
+                    self.parse_declaration()
                 } else {
                     self.parse_statement().map(nodes::BlockItem::Statement)
                 }
             },
+            // only allowed straight inside a block, otherwise it wouldn't be clear which block is meant
+            TokenKind::Identifier(_) if self.at_word("when") && self.peek_word("this")? => self.parse_defer().map(nodes::BlockItem::Statement),
             _ => self.parse_statement().map(nodes::BlockItem::Statement),
         }
     }
 
-    fn parse_declaration(&mut self) -> Result<nodes::Declaration, errors::Error> {
+    fn parse_defer(&mut self) -> Result<nodes::Statement, errors::Error> {
+        // when this block ends comma i shall ...
+        let line_started = self.current_token.line;
+        self.expect_word("when")?;
+        self.expect_word("this")?;
+        self.expect_word("block")?;
+        self.expect_word("ends")?;
+        self.expect(TokenKind::Comma)?;
+        self.expect_keyword(Keyword::I)?;
+        self.expect_keyword(Keyword::Shall)?;
+        let body = self.parse_statement()?;
+        Ok(nodes::Statement { kind: nodes::StatementKind::Defer(Box::new(body)), line_started })
+    }
+
+    fn parse_declaration(&mut self) -> Result<nodes::BlockItem, errors::Error> {
         let line_started = self.current_token.line;
         self.expect_keyword(Keyword::I)?;
         self.expect_keyword(Keyword::Am)?;
         self.expect_keyword(Keyword::Declaring)?;
+        if self.current_token.kind == TokenKind::Keyword(Keyword::The) {
+            return self.parse_destructure(line_started).map(nodes::BlockItem::Destructure);
+        }
         self.expect_keyword(Keyword::A)?;
-        self.expect_keyword(Keyword::Variable)?;
+        let constant = self.at_word("constant");
+        if constant {
+            self.next()?;
+        } else {
+            self.expect_keyword(Keyword::Variable)?;
+        }
         self.expect_keyword(Keyword::Named)?;
         let name = if let TokenKind::Identifier(name) = &self.current_token.kind {
             name.clone()
@@ -200,7 +425,28 @@ impl<'a> Parser<'a> {
         let value = self.parse_expression(0)?;
 
         self.expect(TokenKind::Keyword(Keyword::Period))?;
-        Ok(nodes::Declaration { name, ty, value, line_started })
+        Ok(nodes::BlockItem::Declaration(nodes::Declaration { name, ty, value, line_started, constant }))
+    }
+
+    fn parse_destructure(&mut self, line_started: usize) -> Result<nodes::Destructure, errors::Error> {
+        // i am declaring the variables named {name} is T comma {name} is T shall be equal to {expr} period
+        self.expect_keyword(Keyword::The)?;
+        self.expect_word("variables")?;
+        self.expect_keyword(Keyword::Named)?;
+        let mut names = vec![self.parse_param()?];
+        while self.current_token.kind == TokenKind::Comma {
+            self.next()?;
+            names.push(self.parse_param()?);
+        }
+        self.expect_keyword(Keyword::Shall)?;
+        self.expect_keyword(Keyword::Be)?;
+        self.expect_keyword(Keyword::Equal)?;
+        self.expect_keyword(Keyword::To)?;
+
+        let value = self.parse_expression(0)?;
+
+        self.expect(TokenKind::Keyword(Keyword::Period))?;
+        Ok(nodes::Destructure { names, value, line_started })
     }
 
     fn parse_statement(&mut self) -> Result<nodes::Statement, errors::Error> {
@@ -208,7 +454,12 @@ impl<'a> Parser<'a> {
         Ok(match self.current_token.kind {
             TokenKind::Keyword(Keyword::Return) => {
                 self.next()?;
-                let expr = self.parse_expression(0)?;
+                // functions that return nothing just say return period
+                let expr = if self.current_token.kind == TokenKind::Keyword(Keyword::Period) {
+                    None
+                } else {
+                    Some(self.parse_expression(0)?)
+                };
                 self.expect(TokenKind::Keyword(Keyword::Period))?;
                 nodes::Statement { kind: nodes::StatementKind::Return(expr), line_started }
             }
@@ -219,15 +470,65 @@ impl<'a> Parser<'a> {
                 self.expect_keyword(Keyword::That)?;
                 let cond = self.parse_expression(0)?;
                 self.expect_keyword(Keyword::Do)?;
-                let block = Box::new(self.parse_statement()?);
-                let else_block = if self.current_token.kind == TokenKind::Keyword(Keyword::Or) {
+                let mut branches = vec![(cond, self.parse_statement()?)];
+                let mut else_block = None;
+                // or in the case that X do ... or do ...
+                while self.current_token.kind == TokenKind::Keyword(Keyword::Or) {
                     self.next()?;
-                    self.expect_keyword(Keyword::Do)?;
-                    Some(Box::new(self.parse_statement()?))
-                } else {
-                    None
-                };
-                nodes::Statement { kind: nodes::StatementKind::If(cond, block, else_block), line_started }
+                    if self.current_token.kind == TokenKind::Keyword(Keyword::In) {
+                        self.next()?;
+                        self.expect_keyword(Keyword::The)?;
+                        self.expect_keyword(Keyword::Case)?;
+                        self.expect_keyword(Keyword::That)?;
+                        let cond = self.parse_expression(0)?;
+                        self.expect_keyword(Keyword::Do)?;
+                        branches.push((cond, self.parse_statement()?));
+                    } else {
+                        self.expect_keyword(Keyword::Do)?;
+                        else_block = Some(Box::new(self.parse_statement()?));
+                        break;
+                    }
+                }
+                nodes::Statement { kind: nodes::StatementKind::If(branches, else_block), line_started }
+            }
+            TokenKind::Keyword(Keyword::Do) => {
+                // do ... and keep doing it during the period that X is not zero period
+                self.next()?;
+                let block = self.parse_statement()?;
+                self.expect_keyword(Keyword::And)?;
+                self.expect_word("keep")?;
+                self.expect_word("doing")?;
+                self.expect_keyword(Keyword::It)?;
+                self.expect_keyword(Keyword::During)?;
+                self.expect_keyword(Keyword::The)?;
+                self.expect_keyword(Keyword::Period)?;
+                self.expect_keyword(Keyword::That)?;
+                let cond = self.parse_expression(0)?;
+                self.expect_keyword(Keyword::Is)?;
+                self.expect_keyword(Keyword::Not)?;
+                self.expect_keyword(Keyword::Zero)?;
+                self.expect_keyword(Keyword::Period)?;
+                nodes::Statement { kind: nodes::StatementKind::DoWhile(Box::new(block), cond), line_started }
+            }
+            TokenKind::Identifier(_) if self.at_word("depending") && self.peek_word("on")? => {
+                // depending on X left_parenthesis when it is 1 through 3 do ... otherwise do ... right_parenthesis
+                self.next()?;
+                self.expect_word("on")?;
+                let value = self.parse_expression(0)?;
+                self.expect(TokenKind::LParen)?;
+                let mut cases = Vec::new();
+                let mut default = None;
+                while self.current_token.kind != TokenKind::RParen {
+                    if self.at_word("otherwise") && default.is_none() {
+                        self.next()?;
+                        self.expect_keyword(Keyword::Do)?;
+                        default = Some(Box::new(self.parse_statement()?));
+                    } else {
+                        cases.push(self.parse_switch_case()?);
+                    }
+                }
+                self.expect(TokenKind::RParen)?;
+                nodes::Statement { kind: nodes::StatementKind::Switch(value, cases, default), line_started }
             }
             TokenKind::Keyword(Keyword::During) => {
                 self.next()?;
@@ -242,6 +543,34 @@ impl<'a> Parser<'a> {
                 let block = self.parse_statement()?;
                 nodes::Statement { kind: nodes::StatementKind::While(cond, Box::new(block)), line_started }
             }
+            TokenKind::Keyword(Keyword::I) if self.peek_word("know")? => {
+                // i know for a fact that X is not zero period
+                self.next()?;
+                self.next()?;
+                self.expect_word("for")?;
+                self.expect_keyword(Keyword::A)?;
+                self.expect_word("fact")?;
+                self.expect_keyword(Keyword::That)?;
+                let cond = self.parse_expression(0)?;
+                self.expect_keyword(Keyword::Is)?;
+                self.expect_keyword(Keyword::Not)?;
+                self.expect_keyword(Keyword::Zero)?;
+                self.expect_keyword(Keyword::Period)?;
+                nodes::Statement { kind: nodes::StatementKind::StaticAssert(cond), line_started }
+            }
+            TokenKind::Keyword(Keyword::I) if self.peek_word("insist")? => {
+                // i insist that X is not zero period
+                self.next()?;
+                self.next()?;
+                self.expect_keyword(Keyword::That)?;
+                let cond = self.parse_expression(0)?;
+                self.expect_keyword(Keyword::Is)?;
+                self.expect_keyword(Keyword::Not)?;
+                self.expect_keyword(Keyword::Zero)?;
+                self.expect_keyword(Keyword::Period)?;
+                let source = self.lexer.input.lines().nth(line_started - 1).unwrap_or_default().to_string();
+                nodes::Statement { kind: nodes::StatementKind::Assert(cond, source), line_started }
+            }
             TokenKind::LParen => {
                 let block = self.parse_block()?;
                 nodes::Statement { kind: nodes::StatementKind::Block(block), line_started }
@@ -254,6 +583,23 @@ impl<'a> Parser<'a> {
         })
     }
 
+    fn parse_switch_case(&mut self) -> Result<nodes::SwitchCase, errors::Error> {
+        let line_started = self.current_token.line;
+        self.expect_word("when")?;
+        self.expect_keyword(Keyword::It)?;
+        self.expect_keyword(Keyword::Is)?;
+        let low = self.parse_expression(0)?;
+        let high = if self.at_word("through") {
+            self.next()?;
+            self.parse_expression(0)?
+        } else {
+            low.clone()
+        };
+        self.expect_keyword(Keyword::Do)?;
+        let body = self.parse_statement()?;
+        Ok(nodes::SwitchCase { low, high, body, line_started })
+    }
+
     fn get_prec(&self, kind: &TokenKind) -> i8 {
         match kind {
             TokenKind::Mul | TokenKind::Div | TokenKind::Mod => 50,
@@ -263,10 +609,23 @@ impl<'a> Parser<'a> {
         }
     }
 
+    // "is" only starts a comparison, "is zero" and "is not zero" belong to something else
+    fn binary_prec(&self) -> Result<i8, errors::Error> {
+        if self.current_token.kind == TokenKind::Keyword(Keyword::Is) {
+            return Ok(match self.peek()?.kind {
+                TokenKind::Keyword(Keyword::The) => 30,
+                TokenKind::Identifier(ref name) if name == "less" || name == "more" => 30,
+                _ => -1,
+            });
+        }
+
+        Ok(self.get_prec(&self.current_token.kind))
+    }
+
     fn parse_expression(&mut self, min_prec: i8) -> Result<nodes::Expression, errors::Error> {
         let mut left = self.parse_factor()?;
 
-        let mut prec = self.get_prec(&self.current_token.kind);
+        let mut prec = self.binary_prec()?;
         while prec >= min_prec {
             let line_started = left.line_started;
             let op = match &self.current_token.kind {
@@ -290,7 +649,40 @@ impl<'a> Parser<'a> {
                         ty: nodes::Type::I32
                     };
 
-                    prec = self.get_prec(&self.current_token.kind);
+                    prec = self.binary_prec()?;
+                    continue;
+                }
+                TokenKind::Keyword(Keyword::Is) => {
+                    // is the same as, is less than, is more than
+                    self.next()?;
+                    let op = match self.current_token.kind {
+                        TokenKind::Keyword(Keyword::The) => {
+                            self.next()?;
+                            self.expect_word("same")?;
+                            self.expect_word("as")?;
+                            nodes::Binop::Equal
+                        }
+                        TokenKind::Identifier(_) if self.at_word("less") => {
+                            self.next()?;
+                            self.expect_word("than")?;
+                            nodes::Binop::Less
+                        }
+                        _ => {
+                            self.expect_word("more")?;
+                            self.expect_word("than")?;
+                            nodes::Binop::Greater
+                        }
+                    };
+
+                    let right = self.parse_expression(prec + 1)?;
+
+                    left = nodes::Expression {
+                        kind: nodes::ExpressionKind::Binary(op, Box::new(left), Box::new(right)),
+                        line_started,
+                        ty: nodes::Type::I32
+                    };
+
+                    prec = self.binary_prec()?;
                     continue;
                 }
                 _ => unreachable!("{:?} {} {}", self.current_token.kind, min_prec, prec),
@@ -306,7 +698,7 @@ impl<'a> Parser<'a> {
                 ty: nodes::Type::I32
             };
 
-            prec = self.get_prec(&self.current_token.kind);
+            prec = self.binary_prec()?;
         }
         Ok(left)
     }
@@ -356,30 +748,69 @@ impl<'a> Parser<'a> {
                 self.expect(TokenKind::RBrace)?;
                 Ok(expr)
             }
+            TokenKind::Identifier(_) if self.at_word("tuple") && self.peek()?.kind == TokenKind::Keyword(Keyword::Of) => {
+                // tuple of left_brace {expr} comma {expr} right_brace
+                let line_started = self.current_token.line;
+                self.next()?;
+                self.expect_keyword(Keyword::Of)?;
+                self.expect(TokenKind::LBrace)?;
+                let mut elements = vec![self.parse_expression(0)?];
+                while self.current_token.kind == TokenKind::Comma {
+                    self.next()?;
+                    elements.push(self.parse_expression(0)?);
+                }
+                self.expect(TokenKind::RBrace)?;
+                Ok(nodes::Expression {
+                    kind: nodes::ExpressionKind::Tuple(elements),
+                    line_started,
+                    ty: nodes::Type::I32
+                })
+            }
             TokenKind::Identifier(ref name) => {
                 let name = name.clone();
                 let line_started = self.current_token.line;
                 self.next()?;
+                let name = self.parse_qualifier(name)?;
                 return Ok(nodes::Expression { kind: nodes::ExpressionKind::Variable(name), line_started, ty: nodes::Type::I32 });
             }
             TokenKind::Keyword(Keyword::I) => {
                 // i shall inkove the function named {name} and it shall take the parameters left_brace abc_expr comma def_expr .. right_brace
+                // i shall invoke what {expr} is pointing at and it shall take the parameters ...
                 let line_started = self.current_token.line;
                 self.next()?;
                 self.expect_keyword(Keyword::Shall)?;
+                if self.at_word("request") {
+                    // i shall request room for {expr}
+                    self.next()?;
+                    self.expect_word("room")?;
+                    self.expect_word("for")?;
+                    let expr = self.parse_inner_factor()?;
+                    return Ok(nodes::Expression {
+                        kind: nodes::ExpressionKind::RequestRoom(Box::new(expr)),
+                        line_started,
+                        ty: nodes::Type::I32
+                    });
+                }
                 self.expect_keyword(Keyword::Invoke)?;
-                self.expect_keyword(Keyword::The)?;
-                self.expect_keyword(Keyword::Fn)?;
-                self.expect_keyword(Keyword::Named)?;
-                let fun_name = if let TokenKind::Identifier(name) = &self.current_token.kind {
-                    name.clone()
+                let callee = if self.current_token.kind == TokenKind::Keyword(Keyword::What) {
+                    self.next()?;
+                    let expr = self.parse_inner_factor()?;
+                    self.expect_keyword(Keyword::Is)?;
+                    self.expect_keyword(Keyword::Pointing)?;
+                    self.expect_keyword(Keyword::At)?;
+                    Some(expr)
+                } else {
+                    None
+                };
+                let fun_name = if callee.is_some() {
+                    String::new()
                 } else {
-                    return Err(errors::Error::new(errors::ErrorKind::UnexpectedToken {
-                        expected: "an identifier".to_string(),
-                        found: self.current_token.kind.to_string(),
-                    }, self.current_token.line));
+                    self.expect_keyword(Keyword::The)?;
+                    self.expect_keyword(Keyword::Fn)?;
+                    self.expect_keyword(Keyword::Named)?;
+                    let name = self.parse_identifier()?;
+                    self.parse_qualifier(name)?
                 };
-                self.next()?;
                 self.expect(TokenKind::Keyword(Keyword::And))?;
                 self.expect_keyword(Keyword::It)?;
                 self.expect_keyword(Keyword::Shall)?;
@@ -397,12 +828,30 @@ impl<'a> Parser<'a> {
                 }
                 self.expect(TokenKind::RBrace)?;
 
+                let kind = match callee {
+                    Some(callee) => nodes::ExpressionKind::IndirectCall(Box::new(callee), args),
+                    None => nodes::ExpressionKind::FunctionCall(fun_name, args),
+                };
+
                 Ok(nodes::Expression {
-                    kind: nodes::ExpressionKind::FunctionCall(fun_name, args),
+                    kind,
                     line_started: line_started,
                     ty: nodes::Type::I32
                 })
             }
+            TokenKind::Keyword(Keyword::The) => {
+                // the size of T
+                let line_started = self.current_token.line;
+                self.next()?;
+                self.expect_word("size")?;
+                self.expect_keyword(Keyword::Of)?;
+                let ty = self.parse_type()?;
+                Ok(nodes::Expression {
+                    kind: nodes::ExpressionKind::SizeOf(ty),
+                    line_started,
+                    ty: nodes::Type::I32
+                })
+            }
             TokenKind::Keyword(Keyword::Get) => {
                 let line_started = self.current_token.line;
                 self.next()?;
@@ -2,16 +2,28 @@
 
 #[derive(Debug, Clone)]
 pub struct Program {
+    pub imports: Vec<Import>,
     pub functions: Vec<FunctionDefinition>,
 }
 
+#[derive(Debug, Clone)]
+pub struct Import {
+    pub module: String,
+    pub line_started: usize,
+}
+
 #[derive(Debug, Clone)]
 pub struct FunctionDefinition {
     pub name: String,
+    // generic functions are only templates, the type checker makes a copy for every set of types they get called with
+    pub type_params: Vec<String>,
     pub params: Vec<(String, Type)>,
+    pub variadic: bool,
     pub return_type: Type,
     pub body: Option<Block>,
     pub line_started: usize,
+    // the imported file this came from, None for the file being compiled
+    pub file: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -24,6 +36,7 @@ pub struct Block {
 pub enum BlockItem {
     Statement(Statement),
     Declaration(Declaration),
+    Destructure(Destructure),
 }
 
 #[derive(Debug, Clone)]
@@ -32,6 +45,16 @@ pub struct Declaration {
     pub ty: Type,
     pub value: Expression,
     pub line_started: usize,
+    // constants get folded away before type checking, so later passes never see them
+    pub constant: bool,
+}
+
+// declares one variable for each element of a tuple
+#[derive(Debug, Clone)]
+pub struct Destructure {
+    pub names: Vec<(String, Type)>,
+    pub value: Expression,
+    pub line_started: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -42,11 +65,37 @@ pub struct Statement {
 
 #[derive(Debug, Clone)]
 pub enum StatementKind {
-    Return(Expression),
+    Return(Option<Expression>),
     Block(Block),
     Expression(Expression),
-    If(Expression, Box<Statement>, Option<Box<Statement>>),
+    If(Vec<(Expression, Statement)>, Option<Box<Statement>>),
     While(Expression, Box<Statement>),
+    DoWhile(Box<Statement>, Expression),
+    Switch(Expression, Vec<SwitchCase>, Option<Box<Statement>>),
+    // checked and removed by the constant evaluator
+    StaticAssert(Expression),
+    // checked when the program runs. the string is the line it was written on, shown if it fails
+    Assert(Expression, String),
+    // runs every time the enclosing block is left
+    Defer(Box<Statement>),
+}
+
+#[derive(Debug, Clone)]
+pub struct SwitchCase {
+    pub low: Expression,
+    pub high: Expression,
+    pub body: Statement,
+    pub line_started: usize,
+}
+
+impl SwitchCase {
+    // the labels are plain numbers once the constant evaluator is done with them
+    pub fn range(&self) -> (u64, u64) {
+        match (&self.low.kind, &self.high.kind) {
+            (ExpressionKind::Number(low), ExpressionKind::Number(high)) => (*low, *high),
+            _ => unreachable!(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -64,9 +113,18 @@ pub enum ExpressionKind {
     Assign(Box<Expression>, Box<Expression>),
     IsZero(Box<Expression>),
     FunctionCall(String, Vec<Expression>),
+    IndirectCall(Box<Expression>, Vec<Expression>),
+    RequestRoom(Box<Expression>),
+    // inserted by the ownership pass whenever a box variable gets handed over to someone else
+    Move(Box<Expression>),
     AddressOf(Box<Expression>),
     Dereference(Box<Expression>),
     Subscript(Box<Expression>, Box<Expression>),
+    Tuple(Vec<Expression>),
+    // a pointer to nowhere, it becomes whatever kind of pointer it's used as
+    Null,
+    // turned into a number by the type checker, once any type parameters are filled in
+    SizeOf(Type),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -76,11 +134,43 @@ pub enum Binop {
     Mul,
     Div,
     Mod,
+    Equal,
+    Less,
+    Greater,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Type {
     I32,
     Pointer(Box<Type>),
-    Function(Vec<Type>, Box<Type>),
+    Box(Box<Type>),
+    Function(Vec<Type>, Box<Type>, bool),
+    Tuple(Vec<Type>),
+    // what a function that doesn't give anything back returns. can't be stored anywhere
+    Void,
+    // one of the type parameters of a generic function, gone by the time type checking is done
+    Param(String),
+}
+
+impl Type {
+    // written out the way it would be in a program, for error messages
+    pub fn spelling(&self) -> String {
+        match self {
+            Type::I32 => "integer_meaning_whole_in_latin_with_exactly_thirty_two_bits".to_string(),
+            Type::Void => "nothing".to_string(),
+            Type::Param(name) => name.clone(),
+            Type::Pointer(inner) => format!("pointing at {}", inner.spelling()),
+            Type::Box(inner) => format!("box of {}", inner.spelling()),
+            Type::Tuple(elements) => {
+                format!("tuple of left_brace {} right_brace", elements.iter().map(Type::spelling).collect::<Vec<_>>().join(" comma "))
+            }
+            Type::Function(params, ret, variadic) => {
+                let mut inside = params.iter().map(|param| format!("{} ", param.spelling())).collect::<Vec<_>>().join("comma ");
+                if *variadic {
+                    inside.push_str("and then some more ");
+                }
+                format!("function that shall take the parameters left_brace {}right_brace and return {}", inside, ret.spelling())
+            }
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,404 @@
+use rand::Rng;
+use crate::errors;
+use crate::warnings::{self, Severity};
+
+// what a rule gets to look at. every rule only cares about some of these and lets the rest through
+pub enum Subject<'a> {
+    // a whole file before it's lexed, with which of its lines are inside a note
+    Source { input: &'a str, in_note: &'a [bool] },
+    FunctionNumber { expected: u64, found: u64 },
+    FunctionName(&'a str),
+    // a function with a body, once everything in it has been counted
+    Function { variables: u32 },
+    // the parameters of a function with a body, in the order they're written
+    Parameters(&'a [&'a str]),
+    // a block, and how many blocks it's inside counting itself. a function's body is 1
+    Block { depth: u32 },
+    // how many things a function's blocks do, and how many of them start with please
+    Politeness { polite: u32, items: u32 },
+    VariableName(&'a str),
+    // the compiler starting up, before it's even looked at anything
+    Compile,
+}
+
+pub trait Rule {
+    // what yeller.toml calls it
+    fn name(&self) -> &'static str;
+
+    // what's wrong with the subject, if this rule has a problem with it
+    fn check(&self, subject: &Subject) -> Option<errors::ErrorKind>;
+
+    // changes one of the rule's settings, or says why it can't
+    fn tune(&mut self, setting: &str, _value: &str) -> Result<(), String> {
+        Err(no_setting(self.name(), setting))
+    }
+}
+
+// every rule there is and how seriously each one gets taken. out of the box the old ones are errors
+// and the newer ones are off until yeller.toml asks for them
+pub struct RuleSet {
+    rules: Vec<(Box<dyn Rule>, Severity)>,
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        let rules: Vec<(Box<dyn Rule>, Severity)> = vec![
+            (Box::new(RandomChance { chance: 0.2 }), Severity::Deny),
+            (Box::new(Tabbing), Severity::Deny),
+            (Box::new(ExtraLine), Severity::Deny),
+            (Box::new(FunctionCount), Severity::Deny),
+            (Box::new(LongFunctionName { max_length: 4 }), Severity::Deny),
+            (Box::new(ShortVariableName { min_length: 7 }), Severity::Deny),
+            (Box::new(LonelyVariable), Severity::Deny),
+            (Box::new(PackedFunction { max_variables: 9 }), Severity::Deny),
+            (Box::new(NestingDepth { max_depth: 4 }), Severity::Allow),
+            (Box::new(AlphabeticalParameters), Severity::Allow),
+            (Box::new(Politeness { min_ratio: 0.2 }), Severity::Allow),
+        ];
+
+        Self { rules }
+    }
+}
+
+impl RuleSet {
+    // the first rule that's an error and doesn't like the subject fails it. ones set to warn just complain
+    pub fn check(&self, subject: &Subject, line: usize, warnings: &mut Vec<warnings::Warning>) -> Result<(), errors::Error> {
+        for (rule, severity) in &self.rules {
+            if *severity == Severity::Allow {
+                continue;
+            }
+
+            if let Some(kind) = rule.check(subject) {
+                if *severity == Severity::Deny {
+                    return Err(errors::Error::new(kind, line));
+                }
+                warnings.push(warnings::Warning::new(warnings::WarningKind::BrokenRule(Box::new(kind)), line));
+            }
+        }
+
+        Ok(())
+    }
+
+    // reads the rule sections of a yeller.toml. a rule's level can go straight under [rules], or it can
+    // get a [rules.<name>] table of its own with a level and whatever settings it has. everything else is ignored
+    pub fn configure(&mut self, config: &str) -> Result<(), errors::Error> {
+        // None outside the rules, Some(None) in [rules] and Some(Some(rule)) in a rule's own table
+        let mut section: Option<Option<String>> = None;
+
+        for (index, line) in config.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let result = if let Some(header) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+                let header = header.trim();
+                section = match header.strip_prefix("rules.") {
+                    Some(rule) => Some(Some(rule.to_string())),
+                    None if header == "rules" => Some(None),
+                    None => None,
+                };
+
+                match &section {
+                    Some(Some(rule)) => self.find(rule).map(|_| ()),
+                    _ => Ok(()),
+                }
+            } else if let Some(section) = &section {
+                match line.split_once('=') {
+                    Some((key, value)) => {
+                        let (key, value) = (key.trim(), value.trim().trim_matches('"'));
+                        match section {
+                            None => self.set_level(key, value),
+                            Some(rule) if key == "level" => self.set_level(rule, value),
+                            Some(rule) => self.find(rule).and_then(|(rule, _)| rule.tune(key, value)),
+                        }
+                    }
+                    None => Err(format!("{} is supposed to look like name = value.", line)),
+                }
+            } else {
+                Ok(())
+            };
+
+            result.map_err(|message| errors::Error::new(errors::ErrorKind::BadConfig(message), index + 1))?;
+        }
+
+        Ok(())
+    }
+
+    fn find(&mut self, name: &str) -> Result<&mut (Box<dyn Rule>, Severity), String> {
+        let known = self.rules.iter().map(|(rule, _)| rule.name()).collect::<Vec<_>>().join(", ");
+        self.rules.iter_mut().find(|(rule, _)| rule.name() == name)
+            .ok_or(format!("There's no rule called {}. The ones I know about are {}.", name, known))
+    }
+
+    fn set_level(&mut self, name: &str, level: &str) -> Result<(), String> {
+        let severity = match level {
+            "error" => Severity::Deny,
+            "warn" => Severity::Warn,
+            "off" => Severity::Allow,
+            _ => return Err(format!("{} can be error, warn or off. Not {}.", name, level)),
+        };
+
+        self.find(name)?.1 = severity;
+        Ok(())
+    }
+}
+
+fn no_setting(rule: &str, setting: &str) -> String {
+    format!("{} doesn't have a setting called {}.", rule, setting)
+}
+
+fn number<T: std::str::FromStr>(setting: &str, value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("{} has to be a whole number, and {} isn't one.", setting, value))
+}
+
+// the compiler gets to just not feel like it sometimes
+struct RandomChance {
+    chance: f64,
+}
+
+impl Rule for RandomChance {
+    fn name(&self) -> &'static str {
+        "random-chance"
+    }
+
+    fn check(&self, subject: &Subject) -> Option<errors::ErrorKind> {
+        match subject {
+            Subject::Compile if rand::rng().random_bool(self.chance) => Some(errors::ErrorKind::RandomChance),
+            _ => None,
+        }
+    }
+
+    fn tune(&mut self, setting: &str, value: &str) -> Result<(), String> {
+        match (setting, value.parse::<f64>()) {
+            ("chance", Ok(chance)) if (0.0..=1.0).contains(&chance) => {
+                self.chance = chance;
+                Ok(())
+            }
+            ("chance", _) => Err(format!("chance is how likely it is out of 1, so {} won't work.", value)),
+            _ => Err(no_setting(self.name(), setting)),
+        }
+    }
+}
+
+// no line gets to start with whitespace, unless it's in the middle of a note
+struct Tabbing;
+
+impl Rule for Tabbing {
+    fn name(&self) -> &'static str {
+        "tabbing"
+    }
+
+    fn check(&self, subject: &Subject) -> Option<errors::ErrorKind> {
+        match subject {
+            Subject::Source { input, in_note } => {
+                let tabbed = input.lines().zip(in_note.iter()).any(|(line, in_note)| !in_note && line.starts_with(char::is_whitespace));
+                tabbed.then_some(errors::ErrorKind::Tabbing)
+            }
+            _ => None,
+        }
+    }
+}
+
+struct ExtraLine;
+
+impl Rule for ExtraLine {
+    fn name(&self) -> &'static str {
+        "extra-line"
+    }
+
+    fn check(&self, subject: &Subject) -> Option<errors::ErrorKind> {
+        match subject {
+            Subject::Source { input, in_note } => {
+                let empty = input.lines().zip(in_note.iter()).any(|(line, in_note)| !in_note && line.is_empty());
+                empty.then_some(errors::ErrorKind::ExtraLine)
+            }
+            _ => None,
+        }
+    }
+}
+
+// functions have to be numbered 1, 2, 3 and so on, in the order they're written
+struct FunctionCount;
+
+impl Rule for FunctionCount {
+    fn name(&self) -> &'static str {
+        "function-count"
+    }
+
+    fn check(&self, subject: &Subject) -> Option<errors::ErrorKind> {
+        match *subject {
+            Subject::FunctionNumber { expected, found } if expected != found => Some(errors::ErrorKind::WrongfunctionCount { expected, found }),
+            _ => None,
+        }
+    }
+}
+
+struct LongFunctionName {
+    max_length: usize,
+}
+
+impl Rule for LongFunctionName {
+    fn name(&self) -> &'static str {
+        "long-function-name"
+    }
+
+    fn check(&self, subject: &Subject) -> Option<errors::ErrorKind> {
+        match subject {
+            Subject::FunctionName(name) => {
+                // imported functions are called module.name and generic copies name<types>, only the name part has to be short
+                let local_name = name.split('<').next().unwrap().rsplit('.').next().unwrap();
+                (local_name.len() > self.max_length).then(|| errors::ErrorKind::LongFuncName(name.to_string()))
+            }
+            _ => None,
+        }
+    }
+
+    fn tune(&mut self, setting: &str, value: &str) -> Result<(), String> {
+        match setting {
+            "max-length" => self.max_length = number(setting, value)?,
+            _ => return Err(no_setting(self.name(), setting)),
+        }
+        Ok(())
+    }
+}
+
+struct ShortVariableName {
+    min_length: usize,
+}
+
+impl Rule for ShortVariableName {
+    fn name(&self) -> &'static str {
+        "short-variable-name"
+    }
+
+    fn check(&self, subject: &Subject) -> Option<errors::ErrorKind> {
+        match subject {
+            Subject::VariableName(name) if name.len() < self.min_length => Some(errors::ErrorKind::ShortVarName(name.to_string(), self.min_length)),
+            _ => None,
+        }
+    }
+
+    fn tune(&mut self, setting: &str, value: &str) -> Result<(), String> {
+        match setting {
+            "min-length" => self.min_length = number(setting, value)?,
+            _ => return Err(no_setting(self.name(), setting)),
+        }
+        Ok(())
+    }
+}
+
+// a function with exactly one variable leaves it with nobody to talk to
+struct LonelyVariable;
+
+impl Rule for LonelyVariable {
+    fn name(&self) -> &'static str {
+        "lonely-variable"
+    }
+
+    fn check(&self, subject: &Subject) -> Option<errors::ErrorKind> {
+        match subject {
+            Subject::Function { variables: 1 } => Some(errors::ErrorKind::LonelyVariable),
+            _ => None,
+        }
+    }
+}
+
+struct PackedFunction {
+    max_variables: u32,
+}
+
+impl Rule for PackedFunction {
+    fn name(&self) -> &'static str {
+        "packed-function"
+    }
+
+    fn check(&self, subject: &Subject) -> Option<errors::ErrorKind> {
+        match *subject {
+            Subject::Function { variables } if variables > self.max_variables => Some(errors::ErrorKind::PackedFunc(variables)),
+            _ => None,
+        }
+    }
+
+    fn tune(&mut self, setting: &str, value: &str) -> Result<(), String> {
+        match setting {
+            "max-variables" => self.max_variables = number(setting, value)?,
+            _ => return Err(no_setting(self.name(), setting)),
+        }
+        Ok(())
+    }
+}
+
+struct NestingDepth {
+    max_depth: u32,
+}
+
+impl Rule for NestingDepth {
+    fn name(&self) -> &'static str {
+        "nesting-depth"
+    }
+
+    fn check(&self, subject: &Subject) -> Option<errors::ErrorKind> {
+        match *subject {
+            Subject::Block { depth } if depth > self.max_depth => Some(errors::ErrorKind::TooDeep { depth, max: self.max_depth }),
+            _ => None,
+        }
+    }
+
+    fn tune(&mut self, setting: &str, value: &str) -> Result<(), String> {
+        match setting {
+            "max-depth" => self.max_depth = number(setting, value)?,
+            _ => return Err(no_setting(self.name(), setting)),
+        }
+        Ok(())
+    }
+}
+
+struct AlphabeticalParameters;
+
+impl Rule for AlphabeticalParameters {
+    fn name(&self) -> &'static str {
+        "alphabetical-parameters"
+    }
+
+    fn check(&self, subject: &Subject) -> Option<errors::ErrorKind> {
+        match subject {
+            Subject::Parameters(names) => names.windows(2).find(|pair| pair[0] > pair[1]).map(|pair| errors::ErrorKind::UnsortedParameters {
+                first: pair[0].to_string(),
+                second: pair[1].to_string(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+// like intercal, some of what a function does has to be asked for with a please in front
+struct Politeness {
+    min_ratio: f64,
+}
+
+impl Rule for Politeness {
+    fn name(&self) -> &'static str {
+        "politeness"
+    }
+
+    fn check(&self, subject: &Subject) -> Option<errors::ErrorKind> {
+        match *subject {
+            Subject::Politeness { polite, items } if items > 0 && (polite as f64) < self.min_ratio * items as f64 => {
+                Some(errors::ErrorKind::Impolite { polite, items })
+            }
+            _ => None,
+        }
+    }
+
+    fn tune(&mut self, setting: &str, value: &str) -> Result<(), String> {
+        match (setting, value.parse::<f64>()) {
+            ("min-ratio", Ok(ratio)) if (0.0..=1.0).contains(&ratio) => {
+                self.min_ratio = ratio;
+                Ok(())
+            }
+            ("min-ratio", _) => Err(format!("min-ratio is how much of it has to say please out of 1, so {} won't work.", value)),
+            _ => Err(no_setting(self.name(), setting)),
+        }
+    }
+}
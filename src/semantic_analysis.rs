@@ -1,14 +1,43 @@
 use crate::parser::nodes;
 use crate::errors;
+use crate::warnings;
+use crate::Options;
 
 mod variable_resolution;
+mod const_eval;
+mod control_flow;
 pub mod typecheck;
+mod ownership;
+mod monomorphize;
+mod call_graph;
 
-pub fn analyze(program: nodes::Program) -> Result<(nodes::Program, typecheck::SymbolTable), errors::Error> {
-    let mut analyzer = variable_resolution::Analyzer::new();
+pub use self::variable_resolution::source_name;
+
+// gives back the checked program, what every name turned out to be, and anything worth warning about
+pub fn analyze(program: nodes::Program, options: &Options) -> Result<(nodes::Program, typecheck::SymbolTable, Vec<warnings::Warning>), errors::Error> {
+    let mut analyzer = variable_resolution::Analyzer::new(options.no_shadowing, &options.rules);
     let program = analyzer.analyze_program(program)?;
+    let mut const_evaluator = const_eval::ConstEvaluator::new();
+    let program = const_evaluator.evaluate_program(program)?;
+    let mut control_flow_checker = control_flow::ControlFlowChecker::new();
+    control_flow_checker.check_program(&program)?;
     let mut typechecker = typecheck::TypeChecker::new();
     let program = typechecker.typecheck_program(program)?;
+    let mut ownership_checker = ownership::OwnershipChecker::new();
+    let mut program = ownership_checker.check_program(program)?;
+
+    let mut warnings = analyzer.warnings;
+    warnings.extend(control_flow_checker.warnings);
+
+    // nothing can ever call what main can't reach, so there's no point building it
+    let call_graph = call_graph::CallGraph::new(&program);
+    warnings.extend(call_graph.endless_recursion(&program));
+    let reachable = call_graph.reachable();
+    let (functions, removed): (Vec<_>, Vec<_>) = program.functions.into_iter().partition(|function| function.body.is_none() || reachable.contains(&function.name));
+    program.functions = functions;
+    if options.report_callgraph {
+        print!("{}", call_graph.report(&removed.into_iter().map(|function| function.name).collect::<Vec<_>>()));
+    }
 
-    Ok((program, typechecker.symbol_table))
+    Ok((program, typechecker.symbol_table, warnings))
 }
\ No newline at end of file
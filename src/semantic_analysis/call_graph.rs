@@ -0,0 +1,319 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use crate::parser::nodes;
+use crate::warnings;
+
+// who calls who, worked out once every name means exactly one thing and generics have their copies.
+// a function that's only used as a value still counts as used, it might get called through a pointer
+pub struct CallGraph {
+    // the functions with bodies, in the order they were written
+    functions: Vec<String>,
+    calls: BTreeMap<String, BTreeSet<String>>,
+    // functions each function uses without calling them
+    references: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl CallGraph {
+    pub fn new(program: &nodes::Program) -> Self {
+        let names = program.functions.iter().map(|function| function.name.as_str()).collect::<HashSet<_>>();
+        let mut graph = Self { functions: Vec::new(), calls: BTreeMap::new(), references: BTreeMap::new() };
+
+        for function in &program.functions {
+            let body = match &function.body {
+                Some(body) => body,
+                None => continue,
+            };
+
+            let mut calls = BTreeSet::new();
+            let mut references = BTreeSet::new();
+            block_expressions(body, &mut |expression| names_in(expression, &mut |name, called| {
+                // locals never clash with functions since they've all been renamed to name.N
+                if !names.contains(name) {
+                    return;
+                }
+                if called {
+                    calls.insert(name.to_string());
+                } else {
+                    references.insert(name.to_string());
+                }
+            }));
+
+            graph.functions.push(function.name.clone());
+            graph.calls.insert(function.name.clone(), calls);
+            graph.references.insert(function.name.clone(), references);
+        }
+
+        graph
+    }
+
+    fn uses<'a>(&'a self, function: &str) -> impl Iterator<Item = &'a String> {
+        self.calls.get(function).into_iter().chain(self.references.get(function)).flatten()
+    }
+
+    // everything that can ever run, starting from main. a program without a main could start anywhere
+    pub fn reachable(&self) -> BTreeSet<String> {
+        if !self.functions.iter().any(|function| function == "main") {
+            return self.functions.iter().cloned().collect();
+        }
+
+        let mut reachable = BTreeSet::new();
+        let mut todo = vec!["main".to_string()];
+        while let Some(function) = todo.pop() {
+            if reachable.insert(function.clone()) {
+                todo.extend(self.uses(&function).cloned());
+            }
+        }
+
+        reachable
+    }
+
+    // the functions `function` ends up calling, not counting itself unless it gets back round to it
+    fn called_from(&self, function: &str) -> BTreeSet<String> {
+        let mut called = BTreeSet::new();
+        let mut todo = self.calls.get(function).into_iter().flatten().cloned().collect::<Vec<_>>();
+        while let Some(function) = todo.pop() {
+            if called.insert(function.clone()) {
+                todo.extend(self.calls.get(&function).into_iter().flatten().cloned());
+            }
+        }
+
+        called
+    }
+
+    // groups of functions that call each other round in a circle, in the order the first one was written.
+    // a function that calls itself is a group of one. calls through pointers can't be followed so they don't count
+    pub fn cycles(&self) -> Vec<Vec<String>> {
+        let called = self.functions.iter().map(|function| (function.as_str(), self.called_from(function))).collect::<BTreeMap<_, _>>();
+
+        let mut seen = HashSet::new();
+        let mut cycles = Vec::new();
+        for function in &self.functions {
+            if seen.contains(function) || !called[function.as_str()].contains(function) {
+                continue;
+            }
+
+            let cycle = self.functions.iter()
+                .filter(|other| called[function.as_str()].contains(*other) && called[other.as_str()].contains(function))
+                .cloned().collect::<Vec<_>>();
+            seen.extend(cycle.iter().cloned());
+            cycles.push(cycle);
+        }
+
+        cycles
+    }
+
+    // a circle of calls where every way through every function in it goes round again never stops
+    pub fn endless_recursion(&self, program: &nodes::Program) -> Vec<warnings::Warning> {
+        let mut warnings = Vec::new();
+
+        for cycle in self.cycles() {
+            let members = cycle.iter().cloned().collect::<BTreeSet<_>>();
+            let functions = program.functions.iter().filter(|function| members.contains(&function.name)).collect::<Vec<_>>();
+
+            let stops = functions.iter().any(|function| match &function.body {
+                Some(body) => {
+                    let exits = block_exits(body, &members);
+                    exits.returns || exits.continues
+                }
+                None => true,
+            });
+
+            if !stops {
+                let first = functions[0];
+                warnings.push(warnings::Warning::new(warnings::WarningKind::EndlessRecursion(cycle), first.line_started).in_file(first.file.clone()));
+            }
+        }
+
+        warnings
+    }
+
+    // what --report=callgraph prints
+    pub fn report(&self, removed: &[String]) -> String {
+        let mut report = "Call graph:\n".to_string();
+        for function in &self.functions {
+            let calls = self.calls[function].iter().cloned().collect::<Vec<_>>();
+            let references = self.references[function].iter().cloned().collect::<Vec<_>>();
+            report += &format!("  {} calls {}", function, if calls.is_empty() { "nothing".to_string() } else { calls.join(", ") });
+            if !references.is_empty() {
+                report += &format!(" and uses {}", references.join(", "));
+            }
+            report += "\n";
+        }
+
+        let cycles = self.cycles();
+        if !cycles.is_empty() {
+            report += "Recursion:\n";
+            for cycle in cycles {
+                report += &format!("  {} -> {}\n", cycle.join(" -> "), cycle[0]);
+            }
+        }
+
+        if !removed.is_empty() {
+            report += &format!("Left out because nothing can reach them: {}\n", removed.join(", "));
+        }
+
+        report
+    }
+}
+
+// the ways a piece of code can finish without going back into the cycle
+struct Exits {
+    // some path gets to a return that doesn't call back in
+    returns: bool,
+    // some path carries on to whatever comes next
+    continues: bool,
+}
+
+fn block_exits(block: &nodes::Block, cycle: &BTreeSet<String>) -> Exits {
+    let mut returns = false;
+
+    for item in &block.items {
+        let exits = match item {
+            nodes::BlockItem::Statement(statement) => statement_exits(statement, cycle),
+            nodes::BlockItem::Declaration(declaration) => Exits { returns: false, continues: !calls_into(&declaration.value, cycle) },
+            nodes::BlockItem::Destructure(destructure) => Exits { returns: false, continues: !calls_into(&destructure.value, cycle) },
+        };
+
+        returns |= exits.returns;
+        if !exits.continues {
+            return Exits { returns, continues: false };
+        }
+    }
+
+    Exits { returns, continues: true }
+}
+
+fn statement_exits(statement: &nodes::Statement, cycle: &BTreeSet<String>) -> Exits {
+    match &statement.kind {
+        nodes::StatementKind::Return(value) => Exits { returns: !value.as_ref().is_some_and(|value| calls_into(value, cycle)), continues: false },
+        nodes::StatementKind::Block(block) => block_exits(block, cycle),
+        nodes::StatementKind::Expression(expression) | nodes::StatementKind::Assert(expression, _) | nodes::StatementKind::StaticAssert(expression) => {
+            Exits { returns: false, continues: !calls_into(expression, cycle) }
+        }
+        nodes::StatementKind::If(branches, else_block) => {
+            let mut exits = Exits { returns: false, continues: false };
+            // each condition only gets checked if the ones before it were all zero
+            for (condition, body) in branches {
+                if calls_into(condition, cycle) {
+                    return exits;
+                }
+                let body = statement_exits(body, cycle);
+                exits.returns |= body.returns;
+                exits.continues |= body.continues;
+            }
+
+            match else_block {
+                Some(else_block) => {
+                    let body = statement_exits(else_block, cycle);
+                    exits.returns |= body.returns;
+                    exits.continues |= body.continues;
+                }
+                None => exits.continues = true,
+            }
+            exits
+        }
+        nodes::StatementKind::Switch(value, cases, default) => {
+            if calls_into(value, cycle) {
+                return Exits { returns: false, continues: false };
+            }
+
+            let bodies = cases.iter().map(|case| statement_exits(&case.body, cycle)).chain(default.iter().map(|default| statement_exits(default, cycle))).collect::<Vec<_>>();
+            Exits {
+                returns: bodies.iter().any(|body| body.returns),
+                continues: default.is_none() || bodies.iter().any(|body| body.continues),
+            }
+        }
+        // the body might not run at all, as long as checking the condition doesn't go round again
+        nodes::StatementKind::While(condition, body) => {
+            if calls_into(condition, cycle) {
+                return Exits { returns: false, continues: false };
+            }
+            Exits { returns: statement_exits(body, cycle).returns, continues: true }
+        }
+        nodes::StatementKind::DoWhile(body, condition) => {
+            let body = statement_exits(body, cycle);
+            Exits { returns: body.returns, continues: body.continues && !calls_into(condition, cycle) }
+        }
+        // deferred code runs on the way out, it doesn't change whether there is a way out
+        nodes::StatementKind::Defer(_) => Exits { returns: false, continues: true },
+    }
+}
+
+fn calls_into(expression: &nodes::Expression, cycle: &BTreeSet<String>) -> bool {
+    let mut found = false;
+    names_in(expression, &mut |name, called| found |= called && cycle.contains(name));
+    found
+}
+
+// calls `f` with every expression that appears directly in the block's statements
+fn block_expressions(block: &nodes::Block, f: &mut dyn FnMut(&nodes::Expression)) {
+    for item in &block.items {
+        match item {
+            nodes::BlockItem::Statement(statement) => statement_expressions(statement, f),
+            nodes::BlockItem::Declaration(declaration) => f(&declaration.value),
+            nodes::BlockItem::Destructure(destructure) => f(&destructure.value),
+        }
+    }
+}
+
+fn statement_expressions(statement: &nodes::Statement, f: &mut dyn FnMut(&nodes::Expression)) {
+    match &statement.kind {
+        nodes::StatementKind::Return(None) => {}
+        nodes::StatementKind::Return(Some(expression)) | nodes::StatementKind::Expression(expression) | nodes::StatementKind::StaticAssert(expression)
+            | nodes::StatementKind::Assert(expression, _) => f(expression),
+        nodes::StatementKind::Block(block) => block_expressions(block, f),
+        nodes::StatementKind::Defer(body) => statement_expressions(body, f),
+        nodes::StatementKind::If(branches, else_block) => {
+            for (condition, body) in branches {
+                f(condition);
+                statement_expressions(body, f);
+            }
+            if let Some(else_block) = else_block {
+                statement_expressions(else_block, f);
+            }
+        }
+        nodes::StatementKind::Switch(value, cases, default) => {
+            f(value);
+            for case in cases {
+                statement_expressions(&case.body, f);
+            }
+            if let Some(default) = default {
+                statement_expressions(default, f);
+            }
+        }
+        nodes::StatementKind::While(condition, body) | nodes::StatementKind::DoWhile(body, condition) => {
+            f(condition);
+            statement_expressions(body, f);
+        }
+    }
+}
+
+// calls `f` with every name in the expression, and whether it's being called right there
+fn names_in(expression: &nodes::Expression, f: &mut dyn FnMut(&str, bool)) {
+    match &expression.kind {
+        nodes::ExpressionKind::Number(_) | nodes::ExpressionKind::Null | nodes::ExpressionKind::SizeOf(_) => {}
+        nodes::ExpressionKind::Variable(name) => f(name, false),
+        nodes::ExpressionKind::FunctionCall(name, args) => {
+            f(name, true);
+            for arg in args {
+                names_in(arg, f);
+            }
+        }
+        nodes::ExpressionKind::IndirectCall(callee, args) => {
+            names_in(callee, f);
+            for arg in args {
+                names_in(arg, f);
+            }
+        }
+        nodes::ExpressionKind::Tuple(elements) => {
+            for element in elements {
+                names_in(element, f);
+            }
+        }
+        nodes::ExpressionKind::Binary(_, left, right) | nodes::ExpressionKind::Assign(left, right) | nodes::ExpressionKind::Subscript(left, right) => {
+            names_in(left, f);
+            names_in(right, f);
+        }
+        nodes::ExpressionKind::IsZero(inner) | nodes::ExpressionKind::RequestRoom(inner) | nodes::ExpressionKind::Move(inner)
+            | nodes::ExpressionKind::AddressOf(inner) | nodes::ExpressionKind::Dereference(inner) => names_in(inner, f),
+    }
+}
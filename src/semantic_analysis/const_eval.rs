@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+use crate::parser::nodes;
+use crate::errors;
+
+// works out everything that can be worked out before the program runs. constants, case labels and
+// static assertions have to be, anything else made only of numbers gets folded while we're at it
+pub struct ConstEvaluator {
+    constants: HashMap<String, i32>,
+}
+
+impl ConstEvaluator {
+    pub fn new() -> Self {
+        Self {
+            constants: HashMap::new(),
+        }
+    }
+
+    pub fn evaluate_program(&mut self, mut program: nodes::Program) -> Result<nodes::Program, errors::Error> {
+        for function in &mut program.functions {
+            if let Some(body) = &mut function.body {
+                self.evaluate_block(body).map_err(|e| e.in_file(function.file.clone()))?;
+            }
+        }
+
+        Ok(program)
+    }
+
+    fn evaluate_block(&mut self, block: &mut nodes::Block) -> Result<(), errors::Error> {
+        for item in &mut block.items {
+            match item {
+                nodes::BlockItem::Statement(statement) => self.evaluate_statement(statement)?,
+                nodes::BlockItem::Declaration(declaration) if declaration.constant => {
+                    if declaration.ty != nodes::Type::I32 {
+                        let found = declaration.ty.clone();
+                        return Err(errors::Error::new(errors::ErrorKind::MismatchedTypes { expected: nodes::Type::I32, found }, declaration.line_started));
+                    }
+
+                    let value = self.evaluate_constant(&mut declaration.value)?;
+                    self.constants.insert(declaration.name.clone(), value);
+                }
+                nodes::BlockItem::Declaration(declaration) => self.fold(&mut declaration.value)?,
+                nodes::BlockItem::Destructure(destructure) => self.fold(&mut destructure.value)?,
+            }
+        }
+
+        // constants have all been pasted in where they're used, so there's nothing left to declare
+        block.items.retain(|item| !matches!(item, nodes::BlockItem::Declaration(nodes::Declaration { constant: true, .. })));
+
+        Ok(())
+    }
+
+    fn evaluate_statement(&mut self, statement: &mut nodes::Statement) -> Result<(), errors::Error> {
+        match &mut statement.kind {
+            nodes::StatementKind::Return(None) => {}
+            nodes::StatementKind::Return(Some(expression)) | nodes::StatementKind::Expression(expression)
+                | nodes::StatementKind::Assert(expression, _) => self.fold(expression)?,
+            nodes::StatementKind::Block(block) => self.evaluate_block(block)?,
+            nodes::StatementKind::Defer(body) => self.evaluate_statement(body)?,
+            nodes::StatementKind::If(branches, else_block) => {
+                for (condition, block) in branches {
+                    self.fold(condition)?;
+                    self.evaluate_statement(block)?;
+                }
+                if let Some(block) = else_block {
+                    self.evaluate_statement(block)?;
+                }
+            }
+            nodes::StatementKind::Switch(value, cases, default) => {
+                self.fold(value)?;
+                for case in cases {
+                    self.evaluate_case_label(&mut case.low)?;
+                    self.evaluate_case_label(&mut case.high)?;
+                    self.evaluate_statement(&mut case.body)?;
+                }
+                if let Some(block) = default {
+                    self.evaluate_statement(block)?;
+                }
+            }
+            nodes::StatementKind::While(condition, block) | nodes::StatementKind::DoWhile(block, condition) => {
+                self.fold(condition)?;
+                self.evaluate_statement(block)?;
+            }
+            nodes::StatementKind::StaticAssert(condition) => {
+                if self.evaluate_constant(condition)? == 0 {
+                    return Err(errors::Error::new(errors::ErrorKind::StaticAssertFailed, statement.line_started));
+                }
+
+                // nothing left to do at runtime
+                statement.kind = nodes::StatementKind::Block(nodes::Block { items: Vec::new(), line_started: statement.line_started });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn evaluate_case_label(&mut self, label: &mut nodes::Expression) -> Result<(), errors::Error> {
+        let value = self.evaluate_constant(label)?;
+        label.kind = nodes::ExpressionKind::Number(value as u32 as u64);
+        Ok(())
+    }
+
+    fn evaluate_constant(&mut self, expression: &mut nodes::Expression) -> Result<i32, errors::Error> {
+        self.fold(expression)?;
+
+        match expression.kind {
+            nodes::ExpressionKind::Number(value) => Ok(value as u32 as i32),
+            _ => Err(errors::Error::new(errors::ErrorKind::NotConstant, expression.line_started)),
+        }
+    }
+
+    // replaces every part of the expression that only depends on numbers and constants with its value
+    fn fold(&mut self, expression: &mut nodes::Expression) -> Result<(), errors::Error> {
+        let line = expression.line_started;
+
+        match &mut expression.kind {
+            nodes::ExpressionKind::Number(value) => {
+                // numbers are 32 bits, anything bigger than that can't be written down
+                if *value > u32::MAX as u64 {
+                    return Err(errors::Error::new(errors::ErrorKind::ConstOverflow, line));
+                }
+            }
+            nodes::ExpressionKind::Variable(name) => {
+                if let Some(value) = self.constants.get(name.as_str()) {
+                    expression.kind = nodes::ExpressionKind::Number(*value as u32 as u64);
+                }
+            }
+            nodes::ExpressionKind::Binary(op, left, right) => {
+                self.fold(left)?;
+                self.fold(right)?;
+
+                // this one's going to blow up at runtime no matter what the left side is
+                if matches!(op, nodes::Binop::Div | nodes::Binop::Mod) && right.kind == nodes::ExpressionKind::Number(0) {
+                    return Err(errors::Error::new(errors::ErrorKind::DivideByZero, line));
+                }
+
+                if let (nodes::ExpressionKind::Number(left), nodes::ExpressionKind::Number(right)) = (&left.kind, &right.kind) {
+                    let value = self.evaluate_binary(op, *left as u32 as i32, *right as u32 as i32, line)?;
+                    expression.kind = nodes::ExpressionKind::Number(value as u32 as u64);
+                }
+            }
+            nodes::ExpressionKind::IsZero(inner) => {
+                self.fold(inner)?;
+
+                if let nodes::ExpressionKind::Number(value) = inner.kind {
+                    expression.kind = nodes::ExpressionKind::Number((value == 0) as u64);
+                }
+            }
+            nodes::ExpressionKind::FunctionCall(_, args) => {
+                for arg in args {
+                    self.fold(arg)?;
+                }
+            }
+            nodes::ExpressionKind::IndirectCall(callee, args) => {
+                self.fold(callee)?;
+                for arg in args {
+                    self.fold(arg)?;
+                }
+            }
+            nodes::ExpressionKind::Tuple(elements) => {
+                for element in elements {
+                    self.fold(element)?;
+                }
+            }
+            nodes::ExpressionKind::Assign(left, right) | nodes::ExpressionKind::Subscript(left, right) => {
+                self.fold(left)?;
+                self.fold(right)?;
+            }
+            nodes::ExpressionKind::RequestRoom(inner) | nodes::ExpressionKind::Move(inner)
+                | nodes::ExpressionKind::AddressOf(inner) | nodes::ExpressionKind::Dereference(inner) => {
+                self.fold(inner)?;
+            }
+            // the type checker does these, since the type might still be a type parameter here
+            nodes::ExpressionKind::SizeOf(_) => {}
+            nodes::ExpressionKind::Null => {}
+        }
+
+        Ok(())
+    }
+
+    fn evaluate_binary(&self, op: &nodes::Binop, left: i32, right: i32, line: usize) -> Result<i32, errors::Error> {
+        if matches!(op, nodes::Binop::Div | nodes::Binop::Mod) && right == 0 {
+            return Err(errors::Error::new(errors::ErrorKind::DivideByZero, line));
+        }
+
+        let value = match op {
+            nodes::Binop::Add => left.checked_add(right),
+            nodes::Binop::Sub => left.checked_sub(right),
+            nodes::Binop::Mul => left.checked_mul(right),
+            nodes::Binop::Div => left.checked_div(right),
+            nodes::Binop::Mod => left.checked_rem(right),
+            nodes::Binop::Equal => Some((left == right) as i32),
+            nodes::Binop::Less => Some((left < right) as i32),
+            nodes::Binop::Greater => Some((left > right) as i32),
+        };
+
+        value.ok_or_else(|| errors::Error::new(errors::ErrorKind::ConstOverflow, line))
+    }
+}
@@ -0,0 +1,104 @@
+use crate::parser::nodes;
+use crate::errors;
+use crate::warnings;
+
+// works out which code can actually run. a function that gives something back has to return on
+// every path through it, and anything right after a return is dead weight worth complaining about
+pub struct ControlFlowChecker {
+    pub warnings: Vec<warnings::Warning>,
+    file: Option<String>,
+}
+
+impl ControlFlowChecker {
+    pub fn new() -> Self {
+        Self {
+            warnings: Vec::new(),
+            file: None,
+        }
+    }
+
+    pub fn check_program(&mut self, program: &nodes::Program) -> Result<(), errors::Error> {
+        for function in &program.functions {
+            self.file = function.file.clone();
+            self.check_function(function).map_err(|e| e.in_file(function.file.clone()))?;
+        }
+
+        Ok(())
+    }
+
+    fn check_function(&mut self, function: &nodes::FunctionDefinition) -> Result<(), errors::Error> {
+        let body = match &function.body {
+            Some(body) => body,
+            None => return Ok(()),
+        };
+
+        if self.block_falls_through(body) && function.return_type != nodes::Type::Void {
+            return Err(errors::Error::new(errors::ErrorKind::MissingReturn(function.name.clone()), function.line_started));
+        }
+
+        Ok(())
+    }
+
+    // true if running the block can get to the end of it without returning
+    fn block_falls_through(&mut self, block: &nodes::Block) -> bool {
+        let mut falls_through = true;
+
+        for item in &block.items {
+            if !falls_through {
+                // one warning per block is plenty, the rest of it is just as dead
+                let line = match item {
+                    nodes::BlockItem::Statement(statement) => statement.line_started,
+                    nodes::BlockItem::Declaration(declaration) => declaration.line_started,
+                    nodes::BlockItem::Destructure(destructure) => destructure.line_started,
+                };
+                self.warnings.push(warnings::Warning::new(warnings::WarningKind::UnreachableCode, line).in_file(self.file.clone()));
+                break;
+            }
+
+            if let nodes::BlockItem::Statement(statement) = item {
+                falls_through = self.statement_falls_through(statement);
+            }
+        }
+
+        falls_through
+    }
+
+    fn statement_falls_through(&mut self, statement: &nodes::Statement) -> bool {
+        match &statement.kind {
+            nodes::StatementKind::Return(_) => false,
+            nodes::StatementKind::Block(block) => self.block_falls_through(block),
+            nodes::StatementKind::If(branches, else_block) => {
+                // every branch has to be looked at for dead code, so no short circuiting
+                let mut falls_through = else_block.is_none();
+                for (_, body) in branches {
+                    falls_through |= self.statement_falls_through(body);
+                }
+                if let Some(else_block) = else_block {
+                    falls_through |= self.statement_falls_through(else_block);
+                }
+                falls_through
+            }
+            nodes::StatementKind::Switch(_, cases, default) => {
+                // cases don't run into each other, so it's the same as a chain of ifs
+                let mut falls_through = default.is_none();
+                for case in cases {
+                    falls_through |= self.statement_falls_through(&case.body);
+                }
+                if let Some(default) = default {
+                    falls_through |= self.statement_falls_through(default);
+                }
+                falls_through
+            }
+            // the condition might be zero straight away, so there's no telling if the body ever runs
+            nodes::StatementKind::While(_, body) => {
+                self.statement_falls_through(body);
+                true
+            }
+            // the body runs at least once, and if it always returns the loop never gets to go around
+            nodes::StatementKind::DoWhile(body, _) => self.statement_falls_through(body),
+            // a deferred statement runs when the block ends, it can't return anyway
+            nodes::StatementKind::Defer(_) => true,
+            nodes::StatementKind::Expression(_) | nodes::StatementKind::Assert(..) | nodes::StatementKind::StaticAssert(_) => true,
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use std::collections::{HashMap, HashSet};
+use crate::parser::nodes;
+
+pub type Substitution = HashMap<String, nodes::Type>;
+
+// the name a copy of a generic function gets, like max<i32>. only the part before the '<' counts for long-function-name
+pub fn mangle(name: &str, type_args: &[nodes::Type]) -> String {
+    format!("{}<{}>", name, type_args.iter().map(type_name).collect::<Vec<_>>().join(","))
+}
+
+fn type_name(ty: &nodes::Type) -> String {
+    match ty {
+        nodes::Type::I32 => "i32".to_string(),
+        nodes::Type::Pointer(inner) => format!("*{}", type_name(inner)),
+        nodes::Type::Box(inner) => format!("box<{}>", type_name(inner)),
+        nodes::Type::Tuple(elements) => format!("({})", elements.iter().map(type_name).collect::<Vec<_>>().join(",")),
+        nodes::Type::Function(params, ret, variadic) => {
+            format!("fn({}{})->{}", params.iter().map(type_name).collect::<Vec<_>>().join(","), if *variadic { ",..." } else { "" }, type_name(ret))
+        }
+        nodes::Type::Void => "nothing".to_string(),
+        nodes::Type::Param(name) => name.clone(),
+    }
+}
+
+// works out what the type parameters in `param` must be for it to match `arg`
+pub fn unify(param: &nodes::Type, arg: &nodes::Type, substitution: &mut Substitution) -> bool {
+    match (param, arg) {
+        (nodes::Type::Param(name), _) => match substitution.get(name) {
+            Some(ty) => ty == arg,
+            None => {
+                substitution.insert(name.clone(), arg.clone());
+                true
+            }
+        },
+        (nodes::Type::I32, nodes::Type::I32) | (nodes::Type::Void, nodes::Type::Void) => true,
+        (nodes::Type::Pointer(param), nodes::Type::Pointer(arg)) |
+        (nodes::Type::Box(param), nodes::Type::Box(arg)) => unify(param, arg, substitution),
+        (nodes::Type::Tuple(params), nodes::Type::Tuple(args)) => {
+            params.len() == args.len() && params.iter().zip(args).all(|(param, arg)| unify(param, arg, substitution))
+        }
+        (nodes::Type::Function(params, param_ret, param_variadic), nodes::Type::Function(args, arg_ret, arg_variadic)) => {
+            params.len() == args.len() && param_variadic == arg_variadic
+                && params.iter().zip(args).all(|(param, arg)| unify(param, arg, substitution))
+                && unify(param_ret, arg_ret, substitution)
+        }
+        _ => false,
+    }
+}
+
+pub fn substitute(ty: &nodes::Type, substitution: &Substitution) -> nodes::Type {
+    match ty {
+        nodes::Type::Param(name) => substitution.get(name).cloned().unwrap_or_else(|| ty.clone()),
+        nodes::Type::I32 => nodes::Type::I32,
+        nodes::Type::Void => nodes::Type::Void,
+        nodes::Type::Pointer(inner) => nodes::Type::Pointer(Box::new(substitute(inner, substitution))),
+        nodes::Type::Box(inner) => nodes::Type::Box(Box::new(substitute(inner, substitution))),
+        nodes::Type::Tuple(elements) => nodes::Type::Tuple(elements.iter().map(|element| substitute(element, substitution)).collect()),
+        nodes::Type::Function(params, ret, variadic) => {
+            nodes::Type::Function(params.iter().map(|param| substitute(param, substitution)).collect(), Box::new(substitute(ret, substitution)), *variadic)
+        }
+    }
+}
+
+pub fn contains_param(ty: &nodes::Type) -> bool {
+    match ty {
+        nodes::Type::Param(_) => true,
+        nodes::Type::I32 | nodes::Type::Void => false,
+        nodes::Type::Pointer(inner) | nodes::Type::Box(inner) => contains_param(inner),
+        nodes::Type::Tuple(elements) => elements.iter().any(contains_param),
+        nodes::Type::Function(params, ret, _) => params.iter().any(contains_param) || contains_param(ret),
+    }
+}
+
+// makes the copy of `template` called `name`. its locals get renamed too since the symbol table
+// only knows variables by name, and every copy has them at a different type
+pub fn instantiate(template: &nodes::FunctionDefinition, name: String, substitution: &Substitution) -> nodes::FunctionDefinition {
+    let mut instance = template.clone();
+    let mut locals = HashSet::new();
+
+    for (param, ty) in &mut instance.params {
+        locals.insert(param.clone());
+        *param = format!("{}.{}", param, name);
+        *ty = substitute(ty, substitution);
+    }
+
+    if let Some(body) = &mut instance.body {
+        Instantiator { suffix: &name, substitution, locals }.block(body);
+    }
+
+    instance.return_type = substitute(&instance.return_type, substitution);
+    instance.type_params = Vec::new();
+    instance.name = name;
+    instance
+}
+
+struct Instantiator<'a> {
+    suffix: &'a str,
+    substitution: &'a Substitution,
+    locals: HashSet<String>,
+}
+
+impl<'a> Instantiator<'a> {
+    fn declare(&mut self, name: &mut String, ty: &mut nodes::Type) {
+        self.locals.insert(name.clone());
+        *name = format!("{}.{}", name, self.suffix);
+        *ty = substitute(ty, self.substitution);
+    }
+
+    fn block(&mut self, block: &mut nodes::Block) {
+        for item in &mut block.items {
+            match item {
+                nodes::BlockItem::Statement(statement) => self.statement(statement),
+                nodes::BlockItem::Declaration(declaration) => {
+                    self.expression(&mut declaration.value);
+                    self.declare(&mut declaration.name, &mut declaration.ty);
+                }
+                nodes::BlockItem::Destructure(destructure) => {
+                    self.expression(&mut destructure.value);
+                    for (name, ty) in &mut destructure.names {
+                        self.declare(name, ty);
+                    }
+                }
+            }
+        }
+    }
+
+    fn statement(&mut self, statement: &mut nodes::Statement) {
+        match &mut statement.kind {
+            nodes::StatementKind::Return(None) => {}
+            nodes::StatementKind::Return(Some(expression)) | nodes::StatementKind::Expression(expression) | nodes::StatementKind::StaticAssert(expression)
+                | nodes::StatementKind::Assert(expression, _) => self.expression(expression),
+            nodes::StatementKind::Block(block) => self.block(block),
+            nodes::StatementKind::Defer(body) => self.statement(body),
+            nodes::StatementKind::If(branches, else_block) => {
+                for (condition, block) in branches {
+                    self.expression(condition);
+                    self.statement(block);
+                }
+                if let Some(block) = else_block {
+                    self.statement(block);
+                }
+            }
+            nodes::StatementKind::Switch(value, cases, default) => {
+                self.expression(value);
+                for case in cases {
+                    self.statement(&mut case.body);
+                }
+                if let Some(block) = default {
+                    self.statement(block);
+                }
+            }
+            nodes::StatementKind::While(condition, block) | nodes::StatementKind::DoWhile(block, condition) => {
+                self.expression(condition);
+                self.statement(block);
+            }
+        }
+    }
+
+    fn expression(&mut self, expression: &mut nodes::Expression) {
+        match &mut expression.kind {
+            nodes::ExpressionKind::Number(_) => {}
+            nodes::ExpressionKind::Variable(name) | nodes::ExpressionKind::FunctionCall(name, _) if self.locals.contains(name.as_str()) => {
+                *name = format!("{}.{}", name, self.suffix);
+            }
+            _ => {}
+        }
+
+        match &mut expression.kind {
+            nodes::ExpressionKind::Number(_) | nodes::ExpressionKind::Null | nodes::ExpressionKind::Variable(_) => {}
+            nodes::ExpressionKind::SizeOf(ty) => *ty = substitute(ty, self.substitution),
+            nodes::ExpressionKind::FunctionCall(_, args) => {
+                for arg in args {
+                    self.expression(arg);
+                }
+            }
+            nodes::ExpressionKind::IndirectCall(callee, args) => {
+                self.expression(callee);
+                for arg in args {
+                    self.expression(arg);
+                }
+            }
+            nodes::ExpressionKind::Tuple(elements) => {
+                for element in elements {
+                    self.expression(element);
+                }
+            }
+            nodes::ExpressionKind::Binary(_, left, right) | nodes::ExpressionKind::Assign(left, right) | nodes::ExpressionKind::Subscript(left, right) => {
+                self.expression(left);
+                self.expression(right);
+            }
+            nodes::ExpressionKind::IsZero(inner) | nodes::ExpressionKind::RequestRoom(inner) | nodes::ExpressionKind::Move(inner)
+                | nodes::ExpressionKind::AddressOf(inner) | nodes::ExpressionKind::Dereference(inner) => {
+                self.expression(inner);
+            }
+        }
+    }
+}
@@ -0,0 +1,272 @@
+use std::collections::HashSet;
+use crate::parser::nodes;
+use crate::errors;
+use super::variable_resolution;
+
+// boxes have exactly one owner. handing a box variable to someone else (a declaration, an assignment,
+// a call or a return) moves it, and after that the old name can't be touched until it gets a new box.
+pub struct OwnershipChecker {
+    moved: HashSet<String>,
+    // "when this block ends" bodies seen so far in each block we're inside, they run at the end of it
+    deferred: Vec<Vec<nodes::Statement>>,
+}
+
+impl OwnershipChecker {
+    pub fn new() -> Self {
+        Self {
+            moved: HashSet::new(),
+            deferred: Vec::new(),
+        }
+    }
+
+    pub fn check_program(&mut self, program: nodes::Program) -> Result<nodes::Program, errors::Error> {
+        let new_functions = program.functions.into_iter().map(|function| {
+            let file = function.file.clone();
+            self.check_function(function).map_err(|e| e.in_file(file))
+        }).collect::<Result<Vec<_>, _>>()?;
+
+        Ok(nodes::Program { imports: program.imports, functions: new_functions })
+    }
+
+    fn check_function(&mut self, function: nodes::FunctionDefinition) -> Result<nodes::FunctionDefinition, errors::Error> {
+        self.moved.clear();
+
+        let new_block = if let Some(body) = function.body { Some(self.check_block(body)?) } else { None };
+
+        Ok(nodes::FunctionDefinition {
+            name: function.name,
+            type_params: function.type_params,
+            params: function.params,
+            variadic: function.variadic,
+            return_type: function.return_type,
+            body: new_block,
+            line_started: function.line_started,
+            file: function.file,
+        })
+    }
+
+    fn check_block(&mut self, block: nodes::Block) -> Result<nodes::Block, errors::Error> {
+        let mut new_items = Vec::new();
+        let mut defers = Vec::new();
+        self.deferred.push(Vec::new());
+
+        for item in block.items {
+            match item {
+                nodes::BlockItem::Statement(statement @ nodes::Statement { kind: nodes::StatementKind::Defer(_), .. }) => {
+                    // checked once we know what the end of the block looks like
+                    self.deferred.last_mut().unwrap().push(statement.clone());
+                    defers.push(new_items.len());
+                    new_items.push(nodes::BlockItem::Statement(statement));
+                }
+                nodes::BlockItem::Statement(statement) => {
+                    new_items.push(nodes::BlockItem::Statement(self.check_statement(statement)?));
+                }
+                nodes::BlockItem::Declaration(declaration) => {
+                    let new_value = self.check_expression(declaration.value, true)?;
+                    self.moved.remove(&declaration.name);
+
+                    new_items.push(nodes::BlockItem::Declaration(nodes::Declaration {
+                        name: declaration.name,
+                        ty: declaration.ty,
+                        value: new_value,
+                        line_started: declaration.line_started,
+                        constant: declaration.constant,
+                    }));
+                }
+                nodes::BlockItem::Destructure(destructure) => {
+                    let new_value = self.check_expression(destructure.value, true)?;
+                    for (name, _) in &destructure.names {
+                        self.moved.remove(name);
+                    }
+
+                    new_items.push(nodes::BlockItem::Destructure(nodes::Destructure {
+                        names: destructure.names,
+                        value: new_value,
+                        line_started: destructure.line_started,
+                    }));
+                }
+            }
+        }
+
+        self.deferred.pop();
+        for index in defers.into_iter().rev() {
+            if let nodes::BlockItem::Statement(statement) = &mut new_items[index] {
+                *statement = self.check_deferred(statement.clone())?;
+            }
+        }
+
+        Ok(nodes::Block { items: new_items, line_started: block.line_started })
+    }
+
+    fn check_statement(&mut self, statement: nodes::Statement) -> Result<nodes::Statement, errors::Error> {
+        let kind = match statement.kind {
+            nodes::StatementKind::Return(expression) => {
+                let new_expression = expression.map(|expression| self.check_expression(expression, true)).transpose()?;
+
+                // everything deferred so far runs on the way out
+                let before = self.moved.clone();
+                for deferred in self.deferred.clone().into_iter().rev().flat_map(|deferred| deferred.into_iter().rev()) {
+                    self.check_deferred(deferred)?;
+                }
+                self.moved = before;
+
+                nodes::StatementKind::Return(new_expression)
+            }
+            nodes::StatementKind::Block(block) => {
+                nodes::StatementKind::Block(self.check_block(block)?)
+            }
+            nodes::StatementKind::Expression(expression) => {
+                nodes::StatementKind::Expression(self.check_expression(expression, false)?)
+            }
+            nodes::StatementKind::If(branches, else_block) => {
+                // a box moved in any branch counts as moved afterwards
+                let mut moved_after = HashSet::new();
+                let mut new_branches = Vec::new();
+                for (condition, block) in branches {
+                    let new_condition = self.check_expression(condition, true)?;
+                    let before = self.moved.clone();
+                    let new_block = self.check_statement(block)?;
+                    moved_after.extend(std::mem::replace(&mut self.moved, before));
+                    new_branches.push((new_condition, new_block));
+                }
+                let new_else_block = match else_block {
+                    Some(block) => Some(Box::new(self.check_statement(*block)?)),
+                    None => None,
+                };
+                self.moved.extend(moved_after);
+
+                nodes::StatementKind::If(new_branches, new_else_block)
+            }
+            nodes::StatementKind::Switch(value, cases, default) => {
+                let new_value = self.check_expression(value, true)?;
+                let before = self.moved.clone();
+                let mut moved_after = HashSet::new();
+                let mut new_cases = Vec::new();
+                for case in cases {
+                    let new_body = self.check_statement(case.body)?;
+                    moved_after.extend(std::mem::replace(&mut self.moved, before.clone()));
+                    new_cases.push(nodes::SwitchCase { low: case.low, high: case.high, body: new_body, line_started: case.line_started });
+                }
+                let new_default = match default {
+                    Some(block) => Some(Box::new(self.check_statement(*block)?)),
+                    None => None,
+                };
+                self.moved.extend(moved_after);
+
+                nodes::StatementKind::Switch(new_value, new_cases, new_default)
+            }
+            nodes::StatementKind::While(condition, block) => {
+                // go around twice so moves in one iteration get caught by the next one
+                let new_condition = self.check_expression(condition.clone(), true)?;
+                let new_block = self.check_statement((*block).clone())?;
+                self.check_expression(condition, true)?;
+                self.check_statement(*block)?;
+
+                nodes::StatementKind::While(new_condition, Box::new(new_block))
+            }
+            nodes::StatementKind::DoWhile(block, condition) => {
+                let new_block = self.check_statement((*block).clone())?;
+                let new_condition = self.check_expression(condition.clone(), true)?;
+                self.check_statement(*block)?;
+                self.check_expression(condition, true)?;
+
+                nodes::StatementKind::DoWhile(Box::new(new_block), new_condition)
+            }
+            nodes::StatementKind::Assert(condition, source) => {
+                nodes::StatementKind::Assert(self.check_expression(condition, true)?, source)
+            }
+            nodes::StatementKind::StaticAssert(_) | nodes::StatementKind::Defer(_) => unreachable!(),
+        };
+
+        Ok(nodes::Statement { kind, line_started: statement.line_started })
+    }
+
+    fn check_deferred(&mut self, statement: nodes::Statement) -> Result<nodes::Statement, errors::Error> {
+        let body = match statement.kind {
+            nodes::StatementKind::Defer(body) => self.check_statement(*body)?,
+            _ => unreachable!(),
+        };
+
+        Ok(nodes::Statement { kind: nodes::StatementKind::Defer(Box::new(body)), line_started: statement.line_started })
+    }
+
+    // `value` is true when the result gets handed to someone, which is what moves a box
+    fn check_expression(&mut self, expression: nodes::Expression, value: bool) -> Result<nodes::Expression, errors::Error> {
+        let line_started = expression.line_started;
+        let ty = expression.ty.clone();
+        let is_box = matches!(ty, nodes::Type::Box(_));
+
+        let kind = match expression.kind {
+            nodes::ExpressionKind::Variable(name) => {
+                if self.moved.contains(&name) {
+                    return Err(errors::Error::new(errors::ErrorKind::UseAfterMove(variable_resolution::source_name(&name).to_string()), line_started));
+                }
+
+                if value && is_box {
+                    self.moved.insert(name.clone());
+                    let variable = nodes::Expression { kind: nodes::ExpressionKind::Variable(name), line_started, ty: ty.clone() };
+                    return Ok(nodes::Expression { kind: nodes::ExpressionKind::Move(Box::new(variable)), line_started, ty });
+                }
+
+                nodes::ExpressionKind::Variable(name)
+            }
+            // only fresh boxes and named boxes can be handed over, anything else would end up with two owners
+            _ if value && is_box && !matches!(expression.kind, nodes::ExpressionKind::RequestRoom(_) | nodes::ExpressionKind::FunctionCall(..) | nodes::ExpressionKind::IndirectCall(..)) => {
+                return Err(errors::Error::new(errors::ErrorKind::BoxNotOwned, line_started));
+            }
+            nodes::ExpressionKind::Number(n) => nodes::ExpressionKind::Number(n),
+            nodes::ExpressionKind::Null => nodes::ExpressionKind::Null,
+            nodes::ExpressionKind::Binary(op, left, right) => {
+                let new_left = self.check_expression(*left, true)?;
+                let new_right = self.check_expression(*right, true)?;
+                nodes::ExpressionKind::Binary(op, Box::new(new_left), Box::new(new_right))
+            }
+            nodes::ExpressionKind::Assign(left, right) => {
+                let new_right = self.check_expression(*right, true)?;
+                let new_left = match left.kind {
+                    // assigning a new box brings a moved variable back to life
+                    nodes::ExpressionKind::Variable(name) => {
+                        self.moved.remove(&name);
+                        nodes::Expression { kind: nodes::ExpressionKind::Variable(name), line_started: left.line_started, ty: left.ty }
+                    }
+                    _ => self.check_expression(*left, false)?,
+                };
+                nodes::ExpressionKind::Assign(Box::new(new_left), Box::new(new_right))
+            }
+            nodes::ExpressionKind::IsZero(inner) => {
+                nodes::ExpressionKind::IsZero(Box::new(self.check_expression(*inner, true)?))
+            }
+            nodes::ExpressionKind::FunctionCall(name, args) => {
+                let new_args = args.into_iter().map(|arg| self.check_expression(arg, true)).collect::<Result<Vec<_>, _>>()?;
+                nodes::ExpressionKind::FunctionCall(name, new_args)
+            }
+            nodes::ExpressionKind::IndirectCall(callee, args) => {
+                let new_callee = self.check_expression(*callee, false)?;
+                let new_args = args.into_iter().map(|arg| self.check_expression(arg, true)).collect::<Result<Vec<_>, _>>()?;
+                nodes::ExpressionKind::IndirectCall(Box::new(new_callee), new_args)
+            }
+            nodes::ExpressionKind::RequestRoom(inner) => {
+                nodes::ExpressionKind::RequestRoom(Box::new(self.check_expression(*inner, true)?))
+            }
+            nodes::ExpressionKind::AddressOf(inner) => {
+                nodes::ExpressionKind::AddressOf(Box::new(self.check_expression(*inner, false)?))
+            }
+            nodes::ExpressionKind::Dereference(inner) => {
+                nodes::ExpressionKind::Dereference(Box::new(self.check_expression(*inner, false)?))
+            }
+            nodes::ExpressionKind::Subscript(array, index) => {
+                let new_array = self.check_expression(*array, false)?;
+                let new_index = self.check_expression(*index, true)?;
+                nodes::ExpressionKind::Subscript(Box::new(new_array), Box::new(new_index))
+            }
+            nodes::ExpressionKind::Tuple(elements) => {
+                let new_elements = elements.into_iter().map(|element| self.check_expression(element, true)).collect::<Result<Vec<_>, _>>()?;
+                nodes::ExpressionKind::Tuple(new_elements)
+            }
+            nodes::ExpressionKind::Move(inner) => nodes::ExpressionKind::Move(inner),
+            nodes::ExpressionKind::SizeOf(_) => unreachable!(),
+        };
+
+        Ok(nodes::Expression { kind, line_started, ty })
+    }
+}
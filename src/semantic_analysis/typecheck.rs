@@ -2,6 +2,15 @@ use std::collections::HashMap;
 
 use crate::parser::nodes;
 use crate::errors;
+use crate::llvm_gen;
+use super::monomorphize;
+use super::variable_resolution;
+
+// every value in a case range becomes its own llvm switch case, so keep them small
+const MAX_CASE_RANGE: u64 = 1024;
+
+// a generic function that calls itself with a bigger type each time would never stop making copies
+const MAX_INSTANTIATIONS: usize = 256;
 
 #[derive(Debug, Clone)]
 pub struct STEntry {
@@ -34,12 +43,24 @@ impl SymbolTable {
 
 pub struct TypeChecker {
     pub symbol_table: SymbolTable,
+    templates: HashMap<String, nodes::FunctionDefinition>,
+    instantiated: HashMap<String, nodes::Type>,
+    // copies of generic functions that still need checking
+    pending: Vec<nodes::FunctionDefinition>,
+    // so we know whether a bare return is allowed, and what shape a returned tuple has to be
+    return_type: nodes::Type,
+    sizes: llvm_gen::TypeSizes,
 }
 
 impl TypeChecker {
     pub fn new() -> Self {
         Self {
             symbol_table: SymbolTable::new(),
+            templates: HashMap::new(),
+            instantiated: HashMap::new(),
+            pending: Vec::new(),
+            return_type: nodes::Type::Void,
+            sizes: llvm_gen::TypeSizes::new(),
         }
     }
 
@@ -48,19 +69,47 @@ impl TypeChecker {
             self.preadd_functions(function)?;
         }
 
-        let new_functions = program.functions.into_iter().map(|function| self.typecheck_function(function)).collect::<Result<Vec<_>, _>>()?;
+        // templates only get checked once they've been given real types
+        let mut new_functions = program.functions.into_iter().filter(|function| function.type_params.is_empty()).map(|function| {
+            let file = function.file.clone();
+            self.typecheck_function(function).map_err(|e| e.in_file(file))
+        }).collect::<Result<Vec<_>, _>>()?;
 
-        Ok(nodes::Program { functions: new_functions })
+        // checking a copy can turn up calls that need even more copies
+        while let Some(function) = self.pending.pop() {
+            let file = function.file.clone();
+            new_functions.push(self.typecheck_function(function).map_err(|e| e.in_file(file))?);
+        }
+
+        Ok(nodes::Program { imports: program.imports, functions: new_functions })
     }
 
     fn preadd_functions(&mut self, function: &nodes::FunctionDefinition) -> Result<(), errors::Error> {
-        self.symbol_table.insert_raw(function.name.clone(), nodes::Type::Function(function.params.iter().map(|(_, ty)| ty.clone()).collect(), Box::new(function.return_type.clone())));
+        if !function.type_params.is_empty() {
+            // there's nothing to copy if there's no body
+            if function.body.is_none() {
+                return Err(errors::Error::new(errors::ErrorKind::GenericWithoutBody(function.name.clone()), function.line_started).in_file(function.file.clone()));
+            }
+
+            self.templates.insert(function.name.clone(), function.clone());
+        }
+
+        self.symbol_table.insert_raw(function.name.clone(), nodes::Type::Function(function.params.iter().map(|(_, ty)| ty.clone()).collect(), Box::new(function.return_type.clone()), function.variadic));
 
         Ok(())
     }
 
     fn typecheck_function(&mut self, function: nodes::FunctionDefinition) -> Result<nodes::FunctionDefinition, errors::Error> {
+        if function.return_type != nodes::Type::Void && !self.is_storable(&function.return_type) {
+            return Err(errors::Error::new(errors::ErrorKind::NotStorable(function.return_type), function.line_started));
+        }
+        self.return_type = function.return_type.clone();
+
         for (name, ty) in &function.params {
+            if !self.is_storable(ty) {
+                return Err(errors::Error::new(errors::ErrorKind::NotStorable(ty.clone()), function.line_started));
+            }
+
             self.symbol_table.insert(name.clone(), STEntry { ty: ty.clone() });
         }
 
@@ -68,10 +117,13 @@ impl TypeChecker {
 
         Ok(nodes::FunctionDefinition {
             name: function.name,
+            type_params: function.type_params,
             params: function.params,
+            variadic: function.variadic,
             return_type: function.return_type,
             body: new_block,
-            line_started: function.line_started
+            line_started: function.line_started,
+            file: function.file,
         })
     }
 
@@ -91,23 +143,71 @@ impl TypeChecker {
                 let new_declaration = self.typecheck_declaration(declaration)?;
                 Ok(nodes::BlockItem::Declaration(new_declaration))
             }
+            nodes::BlockItem::Destructure(destructure) => {
+                let new_destructure = self.typecheck_destructure(destructure)?;
+                Ok(nodes::BlockItem::Destructure(new_destructure))
+            }
         }
     }
 
     fn typecheck_declaration(&mut self, declaration: nodes::Declaration) -> Result<nodes::Declaration, errors::Error> {
+        // you can't keep a whole function in a variable, only a pointer to one
+        if !self.is_storable(&declaration.ty) {
+            return Err(errors::Error::new(errors::ErrorKind::NotStorable(declaration.ty), declaration.line_started));
+        }
+
+        // the same rules as assigning to it afterwards, a null can become whatever pointer the variable is
         let new_value = self.typecheck_and_convert(declaration.value)?;
+        let new_value = self.fit(new_value, &declaration.ty);
 
-        // we let llvm catch our type errors because im lazy
+        if new_value.ty != declaration.ty {
+            return Err(errors::Error::new(errors::ErrorKind::MismatchedTypes { expected: declaration.ty, found: new_value.ty }, declaration.line_started));
+        }
+
+        let new_value = self.settle(new_value, &declaration.ty)?;
 
         self.symbol_table.insert(declaration.name.clone(), STEntry { ty: declaration.ty.clone() });
 
-        Ok(nodes::Declaration { name: declaration.name, ty: declaration.ty, value: new_value, line_started: declaration.line_started })
+        Ok(nodes::Declaration { name: declaration.name, ty: declaration.ty, value: new_value, line_started: declaration.line_started, constant: declaration.constant })
+    }
+
+    fn typecheck_destructure(&mut self, destructure: nodes::Destructure) -> Result<nodes::Destructure, errors::Error> {
+        let new_value = self.typecheck_and_convert(destructure.value)?;
+
+        let expected = nodes::Type::Tuple(destructure.names.iter().map(|(_, ty)| ty.clone()).collect());
+        if new_value.ty != expected {
+            return Err(errors::Error::new(errors::ErrorKind::MismatchedTypes { expected, found: new_value.ty }, destructure.line_started));
+        }
+
+        for (name, ty) in &destructure.names {
+            self.symbol_table.insert(name.clone(), STEntry { ty: ty.clone() });
+        }
+
+        Ok(nodes::Destructure { names: destructure.names, value: new_value, line_started: destructure.line_started })
     }
 
     fn typecheck_statement(&mut self, statement: nodes::Statement) -> Result<nodes::Statement, errors::Error> {
         match statement.kind {
             nodes::StatementKind::Return(expression) => {
-                let new_expression = self.typecheck_and_convert(expression)?;
+                // returning something from a function that returns nothing, or the other way around
+                match (&expression, &self.return_type) {
+                    (Some(_), nodes::Type::Void) => return Err(errors::Error::new(errors::ErrorKind::ReturnedFromNothing, statement.line_started)),
+                    (None, nodes::Type::Void) | (Some(_), _) => {}
+                    (None, return_type) => return Err(errors::Error::new(errors::ErrorKind::EmptyReturn(return_type.clone()), statement.line_started)),
+                }
+
+                let new_expression = expression.map(|expression| {
+                    let new_expression = self.typecheck_and_convert(expression)?;
+                    let return_type = self.return_type.clone();
+                    let new_expression = self.fit(new_expression, &return_type);
+
+                    // what comes back has to be what the function promised, tuples down to every last element
+                    if new_expression.ty != return_type {
+                        return Err(errors::Error::new(errors::ErrorKind::MismatchedTypes { expected: return_type, found: new_expression.ty }, new_expression.line_started));
+                    }
+
+                    self.settle(new_expression, &return_type)
+                }).transpose()?;
                 Ok(nodes::Statement { kind: nodes::StatementKind::Return(new_expression), line_started: statement.line_started })
             }
             nodes::StatementKind::Block(block) => {
@@ -115,86 +215,193 @@ impl TypeChecker {
                 Ok(nodes::Statement { kind: nodes::StatementKind::Block(new_block), line_started: statement.line_started })
             }
             nodes::StatementKind::Expression(expression) => {
-                let new_expression = self.typecheck_and_convert(expression)?;
+                // the only place nothing is allowed, since the value gets thrown away anyway
+                let new_expression = self.typecheck_expression(expression)?;
+                let new_expression = self.decay(new_expression);
+
+                if new_expression.ty != nodes::Type::Void && self.mentions_nothing(&new_expression.ty) {
+                    return Err(errors::Error::new(errors::ErrorKind::UntypedNull, statement.line_started));
+                }
                 Ok(nodes::Statement { kind: nodes::StatementKind::Expression(new_expression), line_started: statement.line_started })
             }
-            nodes::StatementKind::If(condition, then_block, else_block) => {
-                let new_condition = self.typecheck_and_convert(condition)?;
-                let new_then_block = self.typecheck_statement(*then_block)?;
+            nodes::StatementKind::If(branches, else_block) => {
+                let new_branches = branches.into_iter().map(|(condition, then_block)| {
+                    Ok((self.typecheck_condition(condition)?, self.typecheck_statement(then_block)?))
+                }).collect::<Result<Vec<_>, _>>()?;
                 let new_else_block = match else_block {
                     Some(else_block) => Some(Box::new(self.typecheck_statement(*else_block)?)),
                     None => None,
                 };
 
-                Ok(nodes::Statement { kind: nodes::StatementKind::If(new_condition, Box::new(new_then_block), new_else_block), line_started: statement.line_started })
+                Ok(nodes::Statement { kind: nodes::StatementKind::If(new_branches, new_else_block), line_started: statement.line_started })
+            }
+            nodes::StatementKind::Switch(value, cases, default) => {
+                let new_value = self.typecheck_and_convert(value)?;
+
+                if !self.is_arithmetic(&new_value.ty) {
+                    return Err(errors::Error::new(errors::ErrorKind::MismatchedTypes { expected: nodes::Type::I32, found: new_value.ty }, statement.line_started));
+                }
+
+                let mut seen: Vec<(u64, u64)> = Vec::new();
+                let mut new_cases = Vec::new();
+                for case in cases {
+                    let (case_low, case_high) = case.range();
+                    if case_low > case_high || case_high - case_low >= MAX_CASE_RANGE {
+                        return Err(errors::Error::new(errors::ErrorKind::BadCaseRange(case_low, case_high), case.line_started));
+                    }
+
+                    if let Some((low, _)) = seen.iter().find(|(low, high)| case_low <= *high && *low <= case_high) {
+                        return Err(errors::Error::new(errors::ErrorKind::DuplicateCase(case_low.max(*low)), case.line_started));
+                    }
+                    seen.push((case_low, case_high));
+
+                    new_cases.push(nodes::SwitchCase {
+                        low: case.low,
+                        high: case.high,
+                        body: self.typecheck_statement(case.body)?,
+                        line_started: case.line_started,
+                    });
+                }
+
+                let new_default = match default {
+                    Some(default) => Some(Box::new(self.typecheck_statement(*default)?)),
+                    None => None,
+                };
+
+                Ok(nodes::Statement { kind: nodes::StatementKind::Switch(new_value, new_cases, new_default), line_started: statement.line_started })
             }
             nodes::StatementKind::While(condition, block) => {
-                let new_condition = self.typecheck_and_convert(condition)?;
+                let new_condition = self.typecheck_condition(condition)?;
                 let new_block = self.typecheck_statement(*block)?;
 
                 Ok(nodes::Statement { kind: nodes::StatementKind::While(new_condition, Box::new(new_block)), line_started: statement.line_started })
             }
+            nodes::StatementKind::DoWhile(block, condition) => {
+                let new_block = self.typecheck_statement(*block)?;
+                let new_condition = self.typecheck_condition(condition)?;
+
+                Ok(nodes::Statement { kind: nodes::StatementKind::DoWhile(Box::new(new_block), new_condition), line_started: statement.line_started })
+            }
+            nodes::StatementKind::Assert(condition, source) => {
+                let new_condition = self.typecheck_condition(condition)?;
+                Ok(nodes::Statement { kind: nodes::StatementKind::Assert(new_condition, source), line_started: statement.line_started })
+            }
+            nodes::StatementKind::Defer(body) => {
+                let new_body = self.typecheck_statement(*body)?;
+                Ok(nodes::Statement { kind: nodes::StatementKind::Defer(Box::new(new_body)), line_started: statement.line_started })
+            }
+            nodes::StatementKind::StaticAssert(_) => unreachable!(),
         }
     }
 
     fn typecheck_expression(&mut self, expression: nodes::Expression) -> Result<nodes::Expression, errors::Error> {
         match expression.kind {
             nodes::ExpressionKind::Number(_) => Ok(expression),
+            // points at nothing in particular until whatever it's used with says otherwise
+            nodes::ExpressionKind::Null => Ok(nodes::Expression { ty: nodes::Type::Pointer(Box::new(nodes::Type::Void)), ..expression }),
+            nodes::ExpressionKind::SizeOf(ty) => {
+                if !self.is_storable(&ty) {
+                    return Err(errors::Error::new(errors::ErrorKind::NotStorable(ty), expression.line_started));
+                }
+
+                // asks the target we're compiling for, so it's right wherever the program ends up running
+                let size = self.sizes.size_of(&ty);
+                Ok(nodes::Expression { kind: nodes::ExpressionKind::Number(size), line_started: expression.line_started, ty: nodes::Type::I32 })
+            }
             nodes::ExpressionKind::Binary(op, left, right) => {
                 let new_left = self.typecheck_and_convert(*left)?;
                 let new_right = self.typecheck_and_convert(*right)?;
 
-                if new_left.ty != new_right.ty {
-                    return Err(errors::Error::new(errors::ErrorKind::TypeError, expression.line_started));
-                }
-
-                let ty = new_left.ty.clone();
+                // null takes on the type of whatever it's compared with
+                let new_left = self.fit(new_left, &new_right.ty);
+                let new_right = self.fit(new_right, &new_left.ty);
+
+                let comparison = matches!(op, nodes::Binop::Equal | nodes::Binop::Less | nodes::Binop::Greater);
+                let ty = match (&op, &new_left.ty, &new_right.ty) {
+                    (_, nodes::Type::I32, nodes::Type::I32) => nodes::Type::I32,
+                    (_, nodes::Type::Pointer(a), nodes::Type::Pointer(b)) if comparison && a == b && !self.mentions_nothing(a) => nodes::Type::I32,
+                    (nodes::Binop::Add | nodes::Binop::Sub, nodes::Type::Pointer(inner), nodes::Type::I32) |
+                    (nodes::Binop::Add, nodes::Type::I32, nodes::Type::Pointer(inner)) if self.is_storable(inner) => nodes::Type::Pointer(inner.clone()),
+                    (nodes::Binop::Sub, nodes::Type::Pointer(a), nodes::Type::Pointer(b)) if a == b && self.is_storable(a) => nodes::Type::I32,
+                    (_, nodes::Type::Pointer(_) | nodes::Type::Box(_), _) | (_, _, nodes::Type::Pointer(_) | nodes::Type::Box(_)) => {
+                        return Err(errors::Error::new(errors::ErrorKind::BadPointerMath, expression.line_started));
+                    }
+                    (_, nodes::Type::I32, found) | (_, found, _) => {
+                        return Err(errors::Error::new(errors::ErrorKind::MismatchedTypes { expected: nodes::Type::I32, found: found.clone() }, expression.line_started));
+                    }
+                };
 
                 Ok(nodes::Expression { kind: nodes::ExpressionKind::Binary(op, Box::new(new_left), Box::new(new_right)), line_started: expression.line_started, ty })
             }
             nodes::ExpressionKind::Variable(ref name) => {
+                // a generic function has no address until it's been called with some types
+                if self.templates.contains_key(name) {
+                    return Err(errors::Error::new(errors::ErrorKind::GenericAsValue(name.clone()), expression.line_started));
+                }
+
                 if let Some(entry) = self.symbol_table.get(name) {
-                    if let nodes::Type::Function(_, _) = entry.ty {
-                        Err(errors::Error::new(errors::ErrorKind::TypeError, expression.line_started))
-                    } else {
-                        Ok(nodes::Expression { kind: nodes::ExpressionKind::Variable(name.clone()), line_started: expression.line_started, ty: entry.ty.clone() })
-                    }
+                    // functions are typed as themselves here, typecheck_and_convert turns them into pointers
+                    Ok(nodes::Expression { kind: nodes::ExpressionKind::Variable(name.clone()), line_started: expression.line_started, ty: entry.ty.clone() })
                 } else {
                     unreachable!()
                 }
             }
+            nodes::ExpressionKind::FunctionCall(name, args) if self.templates.contains_key(&name) => {
+                let new_args = args.into_iter().map(|arg| self.typecheck_and_convert(arg)).collect::<Result<Vec<_>, _>>()?;
+                if new_args.iter().any(|arg| self.mentions_nothing(&arg.ty)) {
+                    return Err(errors::Error::new(errors::ErrorKind::UntypedNull, expression.line_started));
+                }
+                let (name, return_type) = self.instantiate(&name, &new_args, expression.line_started)?;
+
+                Ok(nodes::Expression { kind: nodes::ExpressionKind::FunctionCall(name, new_args), line_started: expression.line_started, ty: return_type })
+            }
             nodes::ExpressionKind::FunctionCall(name, args) => {
                 if let Some(entry) = self.symbol_table.get(&name) {
-                    if let nodes::Type::Function(params, return_type) = &entry.ty {
-                        if args.len() != params.len() {
-                            return Err(errors::Error::new(errors::ErrorKind::TypeError, expression.line_started));
-                        }
+                    match &entry.ty {
+                        nodes::Type::Function(params, return_type, variadic) => {
+                            let params = params.clone();
+                            let return_type = return_type.clone();
+                            let variadic = *variadic;
 
-                        let params = params.iter().cloned().collect::<Vec<_>>();
-                        let return_type = return_type.clone();
+                            let new_args = self.typecheck_call_args(&name, &params, variadic, args, expression.line_started)?;
 
-                        let new_args = args.into_iter().map(|arg| self.typecheck_and_convert(arg)).collect::<Result<Vec<_>, _>>()?;
-
-                        for (arg, param) in new_args.iter().zip(params.iter()) {
-                            if arg.ty != *param {
-                                return Err(errors::Error::new(errors::ErrorKind::TypeError, expression.line_started));
-                            }
+                            Ok(nodes::Expression { kind: nodes::ExpressionKind::FunctionCall(name, new_args), line_started: expression.line_started, ty: *return_type })
                         }
-
-                        Ok(nodes::Expression { kind: nodes::ExpressionKind::FunctionCall(name, new_args), line_started: expression.line_started, ty: *return_type })
-                    } else {
-                        Err(errors::Error::new(errors::ErrorKind::TypeError, expression.line_started))
+                        nodes::Type::Pointer(box nodes::Type::Function(..)) => {
+                            // a variable holding a function pointer, call through it
+                            let callee = nodes::Expression { kind: nodes::ExpressionKind::Variable(name), line_started: expression.line_started, ty: nodes::Type::I32 };
+                            self.typecheck_expression(nodes::Expression { kind: nodes::ExpressionKind::IndirectCall(Box::new(callee), args), line_started: expression.line_started, ty: expression.ty })
+                        }
+                        ty => Err(errors::Error::new(errors::ErrorKind::NotCallable(ty.clone()), expression.line_started)),
                     }
                 } else {
                     unreachable!()
                 }
             }
+            nodes::ExpressionKind::IndirectCall(callee, args) => {
+                let new_callee = self.typecheck_and_convert(*callee)?;
+
+                match &new_callee.ty {
+                    nodes::Type::Pointer(box nodes::Type::Function(params, return_type, variadic)) => {
+                        let params = params.clone();
+                        let ty = *return_type.clone();
+                        let variadic = *variadic;
+
+                        let function = self.callee_name(&new_callee);
+                        let new_args = self.typecheck_call_args(&function, &params, variadic, args, expression.line_started)?;
+
+                        Ok(nodes::Expression { kind: nodes::ExpressionKind::IndirectCall(Box::new(new_callee), new_args), line_started: expression.line_started, ty })
+                    }
+                    ty => Err(errors::Error::new(errors::ErrorKind::NotCallable(ty.clone()), expression.line_started)),
+                }
+            }
             nodes::ExpressionKind::Assign(left, right) => {
                 let new_left = self.typecheck_and_convert(*left)?;
                 let new_right = self.typecheck_and_convert(*right)?;
+                let new_right = self.fit(new_right, &new_left.ty);
 
                 if new_left.ty != new_right.ty {
-                    return Err(errors::Error::new(errors::ErrorKind::TypeError, expression.line_started));
+                    return Err(errors::Error::new(errors::ErrorKind::MismatchedTypes { expected: new_left.ty, found: new_right.ty }, expression.line_started));
                 }
 
                 if !self.is_lvalue(&new_left) {
@@ -209,7 +416,7 @@ impl TypeChecker {
                 let new_expr = self.typecheck_and_convert(*expr)?;
 
                 if !self.is_arithmetic(&new_expr.ty) {
-                    return Err(errors::Error::new(errors::ErrorKind::TypeError, expression.line_started));
+                    return Err(errors::Error::new(errors::ErrorKind::MismatchedTypes { expected: nodes::Type::I32, found: new_expr.ty }, expression.line_started));
                 }
 
                 let ty = new_expr.ty.clone();
@@ -220,16 +427,38 @@ impl TypeChecker {
                 let new_inner = self.typecheck_and_convert(*inner)?;
 
                 match &new_inner.ty {
-                    nodes::Type::Pointer(inner_ty) => Ok({
+                    nodes::Type::Pointer(inner_ty) | nodes::Type::Box(inner_ty) if !self.mentions_nothing(inner_ty) => Ok({
                         let ty = *inner_ty.clone();
                         nodes::Expression { kind: nodes::ExpressionKind::Dereference(Box::new(new_inner)), line_started: expression.line_started, ty }
                     }),
-                    _ => Err(errors::Error::new(errors::ErrorKind::TypeError, expression.line_started))
+                    ty => Err(errors::Error::new(errors::ErrorKind::BadDereference(ty.clone()), expression.line_started))
+                }
+            }
+            nodes::ExpressionKind::RequestRoom(inner) => {
+                let new_inner = self.typecheck_and_convert(*inner)?;
+
+                // boxes in boxes would need their insides freed too, and we don't do that
+                if let nodes::Type::Box(_) = new_inner.ty {
+                    return Err(errors::Error::new(errors::ErrorKind::BoxInBox, expression.line_started));
                 }
+
+                if !self.is_storable(&new_inner.ty) {
+                    return Err(errors::Error::new(errors::ErrorKind::NotStorable(new_inner.ty), expression.line_started));
+                }
+
+                let ty = nodes::Type::Box(Box::new(new_inner.ty.clone()));
+
+                Ok(nodes::Expression { kind: nodes::ExpressionKind::RequestRoom(Box::new(new_inner)), line_started: expression.line_started, ty })
+            }
+            nodes::ExpressionKind::Move(inner) => {
+                let new_inner = self.typecheck_expression(*inner)?;
+                let ty = new_inner.ty.clone();
+
+                Ok(nodes::Expression { kind: nodes::ExpressionKind::Move(Box::new(new_inner)), line_started: expression.line_started, ty })
             }
             nodes::ExpressionKind::AddressOf(inner) => {
                 if !self.is_lvalue(&*inner) {
-                    return Err(errors::Error::new(errors::ErrorKind::TypeError, expression.line_started));
+                    return Err(errors::Error::new(errors::ErrorKind::BadAddressOf, expression.line_started));
                 }
 
                 let new_inner = self.typecheck_expression(*inner)?;
@@ -242,13 +471,13 @@ impl TypeChecker {
                 let new_index = self.typecheck_and_convert(*index)?;
 
                 if !self.is_lvalue(&new_array) {
-                    return Err(errors::Error::new(errors::ErrorKind::TypeError, expression.line_started));
+                    return Err(errors::Error::new(errors::ErrorKind::BadAddressOf, expression.line_started));
                 }
 
                 match &new_array.ty {
                     nodes::Type::Pointer(inner_ty) => {
                         if !self.is_arithmetic(&new_index.ty) {
-                            return Err(errors::Error::new(errors::ErrorKind::TypeError, expression.line_started));
+                            return Err(errors::Error::new(errors::ErrorKind::MismatchedTypes { expected: nodes::Type::I32, found: new_index.ty }, expression.line_started));
                         }
 
                         Ok({
@@ -256,8 +485,18 @@ impl TypeChecker {
                             nodes::Expression { kind: nodes::ExpressionKind::Subscript(Box::new(new_array), Box::new(new_index)), line_started: expression.line_started, ty }
                         })
                     }
-                    _ => Err(errors::Error::new(errors::ErrorKind::TypeError, expression.line_started)),
+                    ty => Err(errors::Error::new(errors::ErrorKind::BadSubscript(ty.clone()), expression.line_started)),
+                }
+            }
+            nodes::ExpressionKind::Tuple(elements) => {
+                let new_elements = elements.into_iter().map(|element| self.typecheck_and_convert(element)).collect::<Result<Vec<_>, _>>()?;
+                let ty = nodes::Type::Tuple(new_elements.iter().map(|element| element.ty.clone()).collect());
+
+                if !self.is_storable(&ty) {
+                    return Err(errors::Error::new(errors::ErrorKind::NotStorable(ty), expression.line_started));
                 }
+
+                Ok(nodes::Expression { kind: nodes::ExpressionKind::Tuple(new_elements), line_started: expression.line_started, ty })
             }
         }
     }
@@ -265,7 +504,169 @@ impl TypeChecker {
     fn typecheck_and_convert(&mut self, expression: nodes::Expression) -> Result<nodes::Expression, errors::Error> {
         let new_expression = self.typecheck_expression(expression)?;
 
-        Ok(new_expression)
+        if new_expression.ty == nodes::Type::Void {
+            return Err(errors::Error::new(errors::ErrorKind::UsedNothing, new_expression.line_started));
+        }
+
+        Ok(self.decay(new_expression))
+    }
+
+    // conditions get checked against zero, so they'd better be numbers
+    fn typecheck_condition(&mut self, condition: nodes::Expression) -> Result<nodes::Expression, errors::Error> {
+        let new_condition = self.typecheck_and_convert(condition)?;
+
+        if !self.is_arithmetic(&new_condition.ty) {
+            return Err(errors::Error::new(errors::ErrorKind::MismatchedTypes { expected: nodes::Type::I32, found: new_condition.ty }, new_condition.line_started));
+        }
+
+        Ok(new_condition)
+    }
+
+    // null doesn't know what it points at, so it borrows the pointer type it's being used as
+    fn fit(&self, expression: nodes::Expression, ty: &nodes::Type) -> nodes::Expression {
+        match (&expression.kind, ty) {
+            (nodes::ExpressionKind::Null, nodes::Type::Pointer(_)) => nodes::Expression { ty: ty.clone(), ..expression },
+            _ => expression,
+        }
+    }
+
+    // like fit, but a null with no pointer to become is an error
+    fn settle(&self, expression: nodes::Expression, ty: &nodes::Type) -> Result<nodes::Expression, errors::Error> {
+        let expression = self.fit(expression, ty);
+
+        if self.mentions_nothing(&expression.ty) {
+            return Err(errors::Error::new(errors::ErrorKind::UntypedNull, expression.line_started));
+        }
+
+        Ok(expression)
+    }
+
+    // functions decay into pointers to themselves whenever they're used as a value
+    fn decay(&self, expression: nodes::Expression) -> nodes::Expression {
+        if let nodes::Type::Function(..) = expression.ty {
+            return match expression.kind {
+                nodes::ExpressionKind::Dereference(inner) => *inner,
+                _ => {
+                    let ty = nodes::Type::Pointer(Box::new(expression.ty.clone()));
+                    let line_started = expression.line_started;
+                    nodes::Expression { kind: nodes::ExpressionKind::AddressOf(Box::new(expression)), line_started, ty }
+                }
+            };
+        }
+
+        expression
+    }
+
+    // works out the type parameters of a generic function from the arguments it's called with, and
+    // queues up a copy of it for those types if there isn't one yet. gives back the copy's name and return type
+    fn instantiate(&mut self, name: &str, args: &[nodes::Expression], line: usize) -> Result<(String, nodes::Type), errors::Error> {
+        let template = &self.templates[name];
+
+        if args.len() != template.params.len() {
+            return Err(errors::Error::new(errors::ErrorKind::WrongArgCount { function: name.to_string(), expected: template.params.len(), found: args.len(), variadic: false }, line));
+        }
+
+        let mut substitution = monomorphize::Substitution::new();
+        for (position, ((_, param), arg)) in template.params.iter().zip(args).enumerate() {
+            if !monomorphize::unify(param, &arg.ty, &mut substitution) {
+                return Err(errors::Error::new(errors::ErrorKind::WrongArgType { function: name.to_string(), position: position + 1, expected: Box::new(param.clone()), found: Box::new(arg.ty.clone()) }, line));
+            }
+        }
+
+        // every type parameter has to show up in the parameters, there's nowhere else to learn it from
+        let type_args = template.type_params.iter().map(|param| substitution.get(param).cloned().ok_or_else(|| {
+            errors::Error::new(errors::ErrorKind::UninferredTypeParam { function: name.to_string(), param: param.clone() }, line)
+        })).collect::<Result<Vec<_>, _>>()?;
+
+        let instance_name = monomorphize::mangle(name, &type_args);
+        if let Some(return_type) = self.instantiated.get(&instance_name) {
+            return Ok((instance_name, return_type.clone()));
+        }
+
+        if self.instantiated.len() >= MAX_INSTANTIATIONS {
+            return Err(errors::Error::new(errors::ErrorKind::TooManyInstances(name.to_string()), line));
+        }
+
+        let instance = monomorphize::instantiate(template, instance_name.clone(), &substitution);
+        let return_type = instance.return_type.clone();
+        self.preadd_functions(&instance)?;
+        self.instantiated.insert(instance_name.clone(), return_type.clone());
+        self.pending.push(instance);
+
+        Ok((instance_name, return_type))
+    }
+
+    fn typecheck_call_args(&mut self, function: &str, params: &[nodes::Type], variadic: bool, args: Vec<nodes::Expression>, line: usize) -> Result<Vec<nodes::Expression>, errors::Error> {
+        if args.len() < params.len() || (!variadic && args.len() != params.len()) {
+            return Err(errors::Error::new(errors::ErrorKind::WrongArgCount { function: function.to_string(), expected: params.len(), found: args.len(), variadic }, line));
+        }
+
+        let new_args = args.into_iter().map(|arg| self.typecheck_and_convert(arg)).collect::<Result<Vec<_>, _>>()?;
+        let new_args = new_args.into_iter().enumerate().map(|(i, arg)| match params.get(i) {
+            Some(param) => self.fit(arg, param),
+            None => arg,
+        }).collect::<Vec<_>>();
+
+        for (position, (arg, param)) in new_args.iter().zip(params.iter()).enumerate() {
+            if arg.ty != *param {
+                return Err(errors::Error::new(errors::ErrorKind::WrongArgType { function: function.to_string(), position: position + 1, expected: Box::new(param.clone()), found: Box::new(arg.ty.clone()) }, line));
+            }
+        }
+
+        // a null handed to printf has nothing to say what it points at
+        if new_args.iter().skip(params.len()).any(|arg| self.mentions_nothing(&arg.ty)) {
+            return Err(errors::Error::new(errors::ErrorKind::UntypedNull, line));
+        }
+
+        // anything past the named params goes through c's default argument promotions, which we don't do.
+        // only hand over things that come out of them unchanged
+        if let Some(position) = new_args.iter().skip(params.len()).position(|arg| !self.survives_promotion(&arg.ty)) {
+            return Err(errors::Error::new(errors::ErrorKind::BadVariadicArg(params.len() + position + 1), line));
+        }
+
+        Ok(new_args)
+    }
+
+    // what to call a function pointer in an error, going by the variable it came out of if there is one
+    fn callee_name(&self, callee: &nodes::Expression) -> String {
+        match &callee.kind {
+            nodes::ExpressionKind::Variable(name) => variable_resolution::source_name(name).to_string(),
+            nodes::ExpressionKind::AddressOf(inner) | nodes::ExpressionKind::Dereference(inner) => self.callee_name(inner),
+            _ => "that function pointer".to_string(),
+        }
+    }
+
+    // ints are already as wide as the promotions make them, and c only promises to read data pointers back out
+    fn survives_promotion(&self, ty: &nodes::Type) -> bool {
+        match ty {
+            nodes::Type::I32 => true,
+            nodes::Type::Pointer(box nodes::Type::Function(..)) => false,
+            nodes::Type::Pointer(_) => true,
+            _ => false,
+        }
+    }
+
+    // functions only get stored as pointers, and boxes stay out of tuples so every box has one obvious owner
+    fn is_storable(&self, ty: &nodes::Type) -> bool {
+        match ty {
+            nodes::Type::Function(..) => false,
+            _ if monomorphize::contains_param(ty) || self.mentions_nothing(ty) => false,
+            nodes::Type::Tuple(elements) => elements.iter().all(|element| !matches!(element, nodes::Type::Box(_)) && self.is_storable(element)),
+            _ => true,
+        }
+    }
+
+    // nothing is only allowed as what a function gives back
+    fn mentions_nothing(&self, ty: &nodes::Type) -> bool {
+        match ty {
+            nodes::Type::Void => true,
+            nodes::Type::I32 | nodes::Type::Param(_) => false,
+            nodes::Type::Pointer(inner) | nodes::Type::Box(inner) => self.mentions_nothing(inner),
+            nodes::Type::Tuple(elements) => elements.iter().any(|element| self.mentions_nothing(element)),
+            nodes::Type::Function(params, ret, _) => {
+                params.iter().any(|param| self.mentions_nothing(param)) || (**ret != nodes::Type::Void && self.mentions_nothing(ret))
+            }
+        }
     }
 
     fn is_arithmetic(&self, ty: &nodes::Type) -> bool {
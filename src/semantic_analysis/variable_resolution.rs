@@ -1,43 +1,185 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use crate::parser::nodes;
 use crate::errors;
+use crate::rules;
+use crate::warnings;
 
 #[derive(Debug, Clone)]
 pub struct VarMapEntry {
     #[allow(dead_code)]
     pub ty: nodes::Type,
+    // what everything after this calls the variable, functions keep their own names
+    pub unique_name: String,
 }
 
-pub struct Analyzer {
-    pub var_map: HashMap<String, VarMapEntry>,
+// locals come out of here as name.N, this gets back the name that was actually written.
+// functions from modules are module.name, but a module name can't start with a digit
+pub fn source_name(name: &str) -> &str {
+    match name.split_once('.') {
+        Some((local, rest)) if rest.starts_with(|c: char| c.is_ascii_digit()) => local,
+        _ => name,
+    }
+}
+
+// how many letters have to be added, removed or swapped for another to turn one name into the other
+fn edit_distance(from: &str, to: &str) -> usize {
+    let to = to.chars().collect::<Vec<_>>();
+    let mut previous = (0..=to.len()).collect::<Vec<_>>();
+
+    for (i, from_char) in from.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, to_char) in to.iter().enumerate() {
+            let swap = previous[j] + if from_char == *to_char { 0 } else { 1 };
+            current.push(swap.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[to.len()]
+}
+
+pub struct Analyzer<'a> {
+    // one map per block, innermost last. the first one holds the functions
+    scopes: Vec<HashMap<String, VarMapEntry>>,
     pub variables_this_function: u32,
+    // how many locals have been renamed so far, so no two get the same name
+    renamed: u32,
+    // a name can't be reused at all, not even in a block inside the one that declared it
+    no_shadowing: bool,
+    // set while looking inside a "when this block ends" statement
+    in_defer: bool,
+    // every name something actually reads or calls, by the name it was given here
+    used: HashSet<String>,
+    // calling yourself doesn't count as being used
+    current_function: String,
+    // the locals of the function being looked at, where they were declared, and whether each one is a parameter
+    locals_this_function: Vec<(String, usize, bool)>,
+    rules: &'a rules::RuleSet,
+    pub warnings: Vec<warnings::Warning>,
 }
 
-impl Analyzer {
-    pub fn new() -> Self {
+impl<'a> Analyzer<'a> {
+    pub fn new(no_shadowing: bool, rules: &'a rules::RuleSet) -> Self {
         Self {
-            var_map: HashMap::new(),
+            scopes: vec![HashMap::new()],
             variables_this_function: 0,
+            renamed: 0,
+            no_shadowing,
+            in_defer: false,
+            used: HashSet::new(),
+            current_function: String::new(),
+            locals_this_function: Vec::new(),
+            rules,
+            warnings: Vec::new(),
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Option<&VarMapEntry> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    fn check_free(&self, name: &str, line: usize) -> Result<(), errors::Error> {
+        let taken = if self.no_shadowing {
+            self.lookup(name).is_some()
+        } else {
+            self.scopes.last().unwrap().contains_key(name)
+        };
+
+        if taken {
+            return Err(errors::Error::new(errors::ErrorKind::VariableAlreadyDeclared(name.to_string()), line));
+        }
+
+        Ok(())
+    }
+
+    // puts a local in the innermost scope and gives back the name it goes by from now on
+    fn declare(&mut self, name: &str, ty: &nodes::Type) -> String {
+        self.renamed += 1;
+        let unique_name = format!("{}.{}", name, self.renamed);
+        self.scopes.last_mut().unwrap().insert(name.to_string(), VarMapEntry { ty: ty.clone(), unique_name: unique_name.clone() });
+        unique_name
+    }
+
+    // the name `name` goes by from here on, for something that gets read or called
+    fn resolve(&mut self, name: String, line: usize) -> Result<String, errors::Error> {
+        let unique_name = self.rename(name, line)?;
+        if unique_name != self.current_function {
+            self.used.insert(unique_name.clone());
         }
+        Ok(unique_name)
+    }
+
+    // like resolve, but for something that only gets written to, which doesn't make it used
+    fn rename(&self, name: String, line: usize) -> Result<String, errors::Error> {
+        match self.lookup(&name).map(|entry| entry.unique_name.clone()) {
+            Some(unique_name) => Ok(unique_name),
+            None => {
+                let suggestion = self.suggest(&name);
+                Err(errors::Error::new(errors::ErrorKind::VariableNotDeclared(name, suggestion), line))
+            }
+        }
+    }
+
+    // the name in sight that's closest to one that isn't, as long as it's close enough to be a typo.
+    // ties go to the innermost scope, then to whichever comes first alphabetically
+    fn suggest(&self, name: &str) -> Option<String> {
+        let most = (name.chars().count() / 3).max(1);
+        let mut best: Option<(usize, &String)> = None;
+
+        for scope in self.scopes.iter().rev() {
+            let mut candidates = scope.keys().collect::<Vec<_>>();
+            candidates.sort();
+            for candidate in candidates {
+                let distance = edit_distance(name, candidate);
+                if distance <= most && best.is_none_or(|(best, _)| distance < best) {
+                    best = Some((distance, candidate));
+                }
+            }
+        }
+
+        best.map(|(_, candidate)| candidate.clone())
     }
 
     pub fn analyze_program(&mut self, mut program: nodes::Program) -> Result<nodes::Program, errors::Error> {
         program.functions.iter().map(|function| {
-            self.preanalyze_function(function)
-        }).collect::<Result<Vec<_>, _>>()?;
+            let start = self.warnings.len();
+            self.preanalyze_function(function).map_err(|e| e.in_file(function.file.clone()))?;
+            self.claim_warnings(start, &function.file);
+            Ok(())
+        }).collect::<Result<Vec<_>, errors::Error>>()?;
         
-        let new_functions = program.functions.into_iter().map(|function| self.analyze_function(function)).collect::<Result<Vec<_>, _>>()?;
+        let new_functions = program.functions.into_iter().map(|function| {
+            let file = function.file.clone();
+            let start = self.warnings.len();
+            let new_function = self.analyze_function(function).map_err(|e| e.in_file(file.clone()))?;
+            self.claim_warnings(start, &file);
+            Ok(new_function)
+        }).collect::<Result<Vec<_>, _>>()?;
+
+        // library functions are there for someone else to use, so only the file being compiled gets this
+        for function in &new_functions {
+            if function.body.is_some() && function.file.is_none() && function.name != "main" && !self.used.contains(&function.name) {
+                self.warnings.push(warnings::Warning::new(warnings::WarningKind::UnusedFunction(function.name.clone()), function.line_started));
+            }
+        }
 
         program.functions = new_functions;
 
         Ok(program)
     }
 
+    // everything warned about since `start` happened in `file`
+    fn claim_warnings(&mut self, start: usize, file: &Option<String>) {
+        let claimed = self.warnings.split_off(start);
+        self.warnings.extend(claimed.into_iter().map(|warning| warning.in_file(file.clone())));
+    }
+
     fn preanalyze_function(&mut self, function: &nodes::FunctionDefinition) -> Result<(), errors::Error> {
-        self.var_map.insert(function.name.clone(), VarMapEntry { ty: nodes::Type::Function(function.params.iter().map(|(_, ty)| ty.clone()).collect(), Box::new(function.return_type.clone())) });
+        let ty = nodes::Type::Function(function.params.iter().map(|(_, ty)| ty.clone()).collect(), Box::new(function.return_type.clone()), function.variadic);
+        self.scopes[0].insert(function.name.clone(), VarMapEntry { ty, unique_name: function.name.clone() });
 
-        if function.name.len() > 4 && function.body.is_some() {
-            return Err(errors::Error::new(errors::ErrorKind::LongFuncName(function.name.clone()), function.line_started));
+        if function.body.is_some() {
+            self.rules.check(&rules::Subject::FunctionName(&function.name), function.line_started, &mut self.warnings)?;
         }
 
         Ok(())
@@ -45,34 +187,64 @@ impl Analyzer {
 
     fn analyze_function(&mut self, function: nodes::FunctionDefinition) -> Result<nodes::FunctionDefinition, errors::Error> {
         self.variables_this_function = 0;
+        self.current_function = function.name.clone();
+
+        // the parameters share a scope with the top of the body
+        self.scopes.push(HashMap::new());
+
+        if function.body.is_some() {
+            let names = function.params.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>();
+            self.rules.check(&rules::Subject::Parameters(&names), function.line_started, &mut self.warnings)?;
+        }
 
-        for (name, ty) in &function.params {
-            self.var_map.insert(name.clone(), VarMapEntry { ty: ty.clone() });
+        let mut new_params = Vec::new();
+        for (name, ty) in function.params {
+            self.check_free(&name, function.line_started)?;
+            let new_name = self.declare(&name, &ty);
+            self.locals_this_function.push((new_name.clone(), function.line_started, true));
+            new_params.push((new_name, ty));
             self.variables_this_function += 1;
         }
 
         let new_block = if let Some(body) = function.body { Some(self.analyze_block(body)?) } else {None};
 
-        if self.variables_this_function == 1 && new_block.is_some() {
-            return Err(errors::Error::new(errors::ErrorKind::LonelyVariable, function.line_started));
+        self.scopes.pop();
+
+        // an extern declaration's parameters are only there to say what it takes
+        if new_block.is_some() {
+            for (name, line, param) in &self.locals_this_function {
+                if !self.used.contains(name) {
+                    let name = source_name(name).to_string();
+                    let kind = if *param { warnings::WarningKind::UnusedParameter(name) } else { warnings::WarningKind::UnusedVariable(name) };
+                    self.warnings.push(warnings::Warning::new(kind, *line));
+                }
+            }
         }
+        self.locals_this_function.clear();
 
-        if self.variables_this_function >= 10 && new_block.is_some() {
-            return Err(errors::Error::new(errors::ErrorKind::PackedFunc(self.variables_this_function), function.line_started));
+        if new_block.is_some() {
+            self.rules.check(&rules::Subject::Function { variables: self.variables_this_function }, function.line_started, &mut self.warnings)?;
         }
 
         let function = nodes::FunctionDefinition {
             name: function.name,
-            params: function.params,
+            type_params: function.type_params,
+            params: new_params,
+            variadic: function.variadic,
             return_type: function.return_type,
             body: new_block,
-            line_started: function.line_started
+            line_started: function.line_started,
+            file: function.file,
         };
 
         Ok(function)
     }
 
     fn analyze_block(&mut self, block: nodes::Block) -> Result<nodes::Block, errors::Error> {
+        // the first scope holds the functions, so a function's own body is one deep
+        let depth = self.scopes.len() as u32 - 1;
+        self.rules.check(&rules::Subject::Block { depth }, block.line_started, &mut self.warnings)?;
+
         let mut new_items = Vec::new();
 
         for item in block.items {
@@ -83,6 +255,9 @@ impl Analyzer {
                 nodes::BlockItem::Declaration(declaration) => {
                     new_items.push(nodes::BlockItem::Declaration(self.analyze_declaration(declaration)?));
                 }
+                nodes::BlockItem::Destructure(destructure) => {
+                    new_items.push(nodes::BlockItem::Destructure(self.analyze_destructure(destructure)?));
+                }
             }
         }
 
@@ -90,33 +265,65 @@ impl Analyzer {
     }
 
     fn analyze_declaration(&mut self, declaration: nodes::Declaration) -> Result<nodes::Declaration, errors::Error> {
-        if self.var_map.contains_key(&declaration.name) {
-            return Err(errors::Error::new(errors::ErrorKind::VariableAlreadyDeclared(declaration.name), declaration.line_started));
-        }
+        self.check_free(&declaration.name, declaration.line_started)?;
 
-        if declaration.name.len() < 7 {
-            return Err(errors::Error::new(errors::ErrorKind::ShortVarName(declaration.name), declaration.line_started));
-        }
+        self.rules.check(&rules::Subject::VariableName(&declaration.name), declaration.line_started, &mut self.warnings)?;
 
         // analyze the expression
         let new_expression = self.analyze_expression(declaration.value)?;
 
-        self.var_map.insert(declaration.name.clone(), VarMapEntry { ty: declaration.ty.clone() });
+        let new_name = self.declare(&declaration.name, &declaration.ty);
+        self.locals_this_function.push((new_name.clone(), declaration.line_started, false));
 
-        self.variables_this_function += 1;
+        // constants don't keep anyone company, they just get pasted in wherever they're used
+        if !declaration.constant {
+            self.variables_this_function += 1;
+        }
 
         Ok(nodes::Declaration {
-            name: declaration.name,
+            name: new_name,
             ty: declaration.ty,
             value: new_expression,
             line_started: declaration.line_started,
+            constant: declaration.constant,
+        })
+    }
+
+    fn analyze_destructure(&mut self, destructure: nodes::Destructure) -> Result<nodes::Destructure, errors::Error> {
+        for (name, _) in &destructure.names {
+            self.check_free(name, destructure.line_started)?;
+
+            if destructure.names.iter().filter(|(other, _)| other == name).count() > 1 {
+                return Err(errors::Error::new(errors::ErrorKind::VariableAlreadyDeclared(name.clone()), destructure.line_started));
+            }
+
+            self.rules.check(&rules::Subject::VariableName(name), destructure.line_started, &mut self.warnings)?;
+        }
+
+        let new_expression = self.analyze_expression(destructure.value)?;
+
+        let line_started = destructure.line_started;
+        let new_names = destructure.names.into_iter().map(|(name, ty)| {
+            self.variables_this_function += 1;
+            let new_name = self.declare(&name, &ty);
+            self.locals_this_function.push((new_name.clone(), line_started, false));
+            (new_name, ty)
+        }).collect();
+
+        Ok(nodes::Destructure {
+            names: new_names,
+            value: new_expression,
+            line_started: destructure.line_started,
         })
     }
 
     fn analyze_statement(&mut self, statement: nodes::Statement) -> Result<nodes::Statement, errors::Error> {
         match statement.kind {
+            nodes::StatementKind::Return(_) if self.in_defer => {
+                Err(errors::Error::new(errors::ErrorKind::ReturnInDefer, statement.line_started))
+            }
             nodes::StatementKind::Return(expression) => {
-                let new_expression = self.analyze_expression(expression)?;
+                let new_expression = expression.map(|expression| self.analyze_expression(expression)).transpose()?;
 
                 Ok(nodes::Statement {
                     kind: nodes::StatementKind::Return(new_expression),
@@ -124,7 +331,9 @@ impl Analyzer {
                 })
             }
             nodes::StatementKind::Block(block) => {
+                self.scopes.push(HashMap::new());
                 let new_block = self.analyze_block(block)?;
+                self.scopes.pop();
 
                 Ok(nodes::Statement {
                     kind: nodes::StatementKind::Block(new_block),
@@ -139,13 +348,31 @@ impl Analyzer {
                     line_started: statement.line_started,
                 })
             }
-            nodes::StatementKind::If(val, block, else_block) => {
-                let new_val = self.analyze_expression(val)?;
-                let new_block = self.analyze_statement(*block)?;
+            nodes::StatementKind::If(branches, else_block) => {
+                let new_branches = branches.into_iter().map(|(val, block)| {
+                    Ok((self.analyze_expression(val)?, self.analyze_statement(block)?))
+                }).collect::<Result<Vec<_>, _>>()?;
                 let new_else_block = else_block.map(|block| self.analyze_statement(*block)).transpose()?;
 
                 Ok(nodes::Statement {
-                    kind: nodes::StatementKind::If(new_val, Box::new(new_block), new_else_block.map(Box::new)),
+                    kind: nodes::StatementKind::If(new_branches, new_else_block.map(Box::new)),
+                    line_started: statement.line_started,
+                })
+            }
+            nodes::StatementKind::Switch(val, cases, default) => {
+                let new_val = self.analyze_expression(val)?;
+                let new_cases = cases.into_iter().map(|case| {
+                    Ok(nodes::SwitchCase {
+                        low: self.analyze_expression(case.low)?,
+                        high: self.analyze_expression(case.high)?,
+                        body: self.analyze_statement(case.body)?,
+                        line_started: case.line_started,
+                    })
+                }).collect::<Result<Vec<_>, _>>()?;
+                let new_default = default.map(|block| self.analyze_statement(*block)).transpose()?;
+
+                Ok(nodes::Statement {
+                    kind: nodes::StatementKind::Switch(new_val, new_cases, new_default.map(Box::new)),
                     line_started: statement.line_started,
                 })
             }
@@ -158,12 +385,47 @@ impl Analyzer {
                     line_started: statement.line_started,
                 })
             }
+            nodes::StatementKind::DoWhile(block, val) => {
+                let new_block = self.analyze_statement(*block)?;
+                let new_val = self.analyze_expression(val)?;
+
+                Ok(nodes::Statement {
+                    kind: nodes::StatementKind::DoWhile(Box::new(new_block), new_val),
+                    line_started: statement.line_started,
+                })
+            }
+            nodes::StatementKind::StaticAssert(val) => {
+                let new_val = self.analyze_expression(val)?;
+
+                Ok(nodes::Statement {
+                    kind: nodes::StatementKind::StaticAssert(new_val),
+                    line_started: statement.line_started,
+                })
+            }
+            nodes::StatementKind::Assert(val, source) => {
+                let new_val = self.analyze_expression(val)?;
+
+                Ok(nodes::Statement {
+                    kind: nodes::StatementKind::Assert(new_val, source),
+                    line_started: statement.line_started,
+                })
+            }
+            nodes::StatementKind::Defer(body) => {
+                let was_in_defer = std::mem::replace(&mut self.in_defer, true);
+                let new_body = self.analyze_statement(*body);
+                self.in_defer = was_in_defer;
+
+                Ok(nodes::Statement {
+                    kind: nodes::StatementKind::Defer(Box::new(new_body?)),
+                    line_started: statement.line_started,
+                })
+            }
         }
     }
 
     fn analyze_expression(&mut self, expression: nodes::Expression) -> Result<nodes::Expression, errors::Error> {
         match expression.kind {
-            nodes::ExpressionKind::Number(_) => Ok(expression),
+            nodes::ExpressionKind::Number(_) | nodes::ExpressionKind::Null | nodes::ExpressionKind::SizeOf(_) => Ok(expression),
             nodes::ExpressionKind::Binary(op, left, right) => {
                 let new_left = self.analyze_expression(*left)?;
                 let new_right = self.analyze_expression(*right)?;
@@ -175,18 +437,29 @@ impl Analyzer {
                 })
             }
             nodes::ExpressionKind::Variable(name) => {
-                if !self.var_map.contains_key(&name) {
-                    return Err(errors::Error::new(errors::ErrorKind::VariableNotDeclared(name), expression.line_started));
+                // the parser can't tell, so null is the null pointer unless something by that name is in scope
+                if name == "null" && self.lookup(&name).is_none() {
+                    return Ok(nodes::Expression { kind: nodes::ExpressionKind::Null, line_started: expression.line_started, ty: expression.ty });
                 }
 
+                let new_name = self.resolve(name, expression.line_started)?;
+
                 Ok(nodes::Expression {
-                    kind: nodes::ExpressionKind::Variable(name),
+                    kind: nodes::ExpressionKind::Variable(new_name),
                     line_started: expression.line_started,
                     ty: expression.ty,
                 })
             }
             nodes::ExpressionKind::Assign(left, right) => {
-                let new_left = self.analyze_expression(*left)?;
+                // putting something in a variable isn't using it, reading what's there is
+                let new_left = match left.kind {
+                    nodes::ExpressionKind::Variable(name) => nodes::Expression {
+                        kind: nodes::ExpressionKind::Variable(self.rename(name, left.line_started)?),
+                        line_started: left.line_started,
+                        ty: left.ty,
+                    },
+                    _ => self.analyze_expression(*left)?,
+                };
                 let new_right = self.analyze_expression(*right)?;
 
                 Ok(nodes::Expression {
@@ -205,14 +478,40 @@ impl Analyzer {
                 })
             }
             nodes::ExpressionKind::FunctionCall(name, args) => {
-                if !self.var_map.contains_key(&name) {
-                    return Err(errors::Error::new(errors::ErrorKind::VariableNotDeclared(name), expression.line_started));
-                }
+                // might be a local holding a function pointer, those get renamed like any other
+                let new_name = self.resolve(name, expression.line_started)?;
+                let new_args = args.into_iter().map(|arg| self.analyze_expression(arg)).collect::<Result<Vec<_>, _>>()?;
 
+                Ok(nodes::Expression {
+                    kind: nodes::ExpressionKind::FunctionCall(new_name, new_args),
+                    line_started: expression.line_started,
+                    ty: expression.ty,
+                })
+            }
+            nodes::ExpressionKind::IndirectCall(callee, args) => {
+                let new_callee = self.analyze_expression(*callee)?;
                 let new_args = args.into_iter().map(|arg| self.analyze_expression(arg)).collect::<Result<Vec<_>, _>>()?;
 
                 Ok(nodes::Expression {
-                    kind: nodes::ExpressionKind::FunctionCall(name, new_args),
+                    kind: nodes::ExpressionKind::IndirectCall(Box::new(new_callee), new_args),
+                    line_started: expression.line_started,
+                    ty: expression.ty,
+                })
+            }
+            nodes::ExpressionKind::RequestRoom(expr) => {
+                let new_expr = self.analyze_expression(*expr)?;
+
+                Ok(nodes::Expression {
+                    kind: nodes::ExpressionKind::RequestRoom(Box::new(new_expr)),
+                    line_started: expression.line_started,
+                    ty: expression.ty,
+                })
+            }
+            nodes::ExpressionKind::Move(expr) => {
+                let new_expr = self.analyze_expression(*expr)?;
+
+                Ok(nodes::Expression {
+                    kind: nodes::ExpressionKind::Move(Box::new(new_expr)),
                     line_started: expression.line_started,
                     ty: expression.ty,
                 })
@@ -245,6 +544,35 @@ impl Analyzer {
                     ty: expression.ty,
                 })
             }
+            nodes::ExpressionKind::Tuple(elements) => {
+                let new_elements = elements.into_iter().map(|element| self.analyze_expression(element)).collect::<Result<Vec<_>, _>>()?;
+
+                Ok(nodes::Expression {
+                    kind: nodes::ExpressionKind::Tuple(new_elements),
+                    line_started: expression.line_started,
+                    ty: expression.ty,
+                })
+            }
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules;
+
+    #[test]
+    fn test_rule_warnings_know_their_file() {
+        let mut rules = rules::RuleSet::default();
+        rules.configure("[rules]\nshort-variable-name = \"warn\"").unwrap();
+        let (program, _) = modules::ModuleLoader::new(&rules).load_program("module_examples/shadowed.yl").unwrap();
+
+        let mut analyzer = Analyzer::new(false, &rules);
+        analyzer.analyze_program(program).unwrap();
+
+        // the short name is in the module, not the file being compiled
+        let broken = analyzer.warnings.iter().find(|warning| matches!(&warning.kind, warnings::WarningKind::BrokenRule(kind) if matches!(**kind, errors::ErrorKind::ShortVarName(..)))).unwrap();
+        assert_eq!(broken.file.as_deref(), Some("module_examples/helpr.yl"));
+    }
+}
@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+use crate::errors;
+
+// things that are probably mistakes but don't stop the program from compiling. each kind is a lint
+// with a name, and --allow or --deny decide what happens to it
+#[derive(Debug, Clone)]
+pub struct Warning {
+    pub kind: WarningKind,
+    pub line: usize,
+    // set when the warning is in an imported file instead of the one being compiled
+    pub file: Option<String>,
+}
+
+impl Warning {
+    pub fn new(kind: WarningKind, line: usize) -> Self {
+        Self { kind, line, file: None }
+    }
+
+    pub fn in_file(mut self, file: Option<String>) -> Self {
+        if self.file.is_none() {
+            self.file = file;
+        }
+        self
+    }
+
+    // warnings get printed but not read out loud, nobody wants to listen to those
+    pub fn report(&self, input: &str) {
+        eprintln!("Warning: {}", self.kind.text());
+        eprintln!("{}", errors::source_line(self.line, input));
+    }
+
+    // what a denied warning turns into
+    pub fn into_error(self) -> errors::Error {
+        errors::Error::new(errors::ErrorKind::DeniedWarning(self.kind), self.line).in_file(self.file)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WarningKind {
+    UnreachableCode,
+    UnusedVariable(String),
+    UnusedParameter(String),
+    UnusedFunction(String),
+    DeadAssignment(String),
+    // functions that keep calling each other with no way to stop, in the order they were written
+    EndlessRecursion(Vec<String>),
+    // a house rule that yeller.toml turned down to a warning
+    BrokenRule(Box<errors::ErrorKind>),
+}
+
+// every lint there is, by the name the command line uses
+pub const LINTS: &[&str] = &["unreachable-code", "unused-variable", "unused-parameter", "unused-function", "dead-assignment", "endless-recursion", "house-rule"];
+
+impl WarningKind {
+    pub fn lint(&self) -> &'static str {
+        match self {
+            Self::UnreachableCode => "unreachable-code",
+            Self::UnusedVariable(_) => "unused-variable",
+            Self::UnusedParameter(_) => "unused-parameter",
+            Self::UnusedFunction(_) => "unused-function",
+            Self::DeadAssignment(_) => "dead-assignment",
+            Self::EndlessRecursion(_) => "endless-recursion",
+            Self::BrokenRule(_) => "house-rule",
+        }
+    }
+
+    pub fn text(&self) -> String {
+        match self {
+            Self::UnreachableCode => {
+                "Nothing after a return ever runs. You wrote this for nobody.".to_string()
+            }
+            Self::UnusedVariable(name) => {
+                format!("You made a whole variable called {} and then never looked at it again. It had a family.", name)
+            }
+            Self::UnusedParameter(name) => {
+                format!("Everyone who calls this has to hand over {}, and you just ignore it. Rude.", name)
+            }
+            Self::UnusedFunction(name) => {
+                format!("Nobody ever invokes {}. Not even you. Why does it exist?", name)
+            }
+            Self::DeadAssignment(name) => {
+                format!("Whatever you just put in {} gets thrown away before anyone reads it. Pointless.", name)
+            }
+            Self::EndlessRecursion(functions) if functions.len() == 1 => {
+                format!("{} calls itself every single time and never once stops. Enjoy your stack overflow.", functions[0])
+            }
+            Self::EndlessRecursion(functions) => {
+                format!("{} just keep calling each other forever. Somebody has to hang up first.", functions.join(" and "))
+            }
+            Self::BrokenRule(kind) => kind.text(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Allow,
+    Warn,
+    Deny,
+}
+
+// what each lint has been set to, anything not mentioned just warns
+#[derive(Debug, Clone, Default)]
+pub struct LintLevels {
+    levels: HashMap<String, Severity>,
+}
+
+impl LintLevels {
+    // gives back false if there's no lint with that name
+    pub fn set(&mut self, lint: &str, severity: Severity) -> bool {
+        if !LINTS.contains(&lint) {
+            return false;
+        }
+
+        self.levels.insert(lint.to_string(), severity);
+        true
+    }
+
+    pub fn level(&self, lint: &str) -> Severity {
+        self.levels.get(lint).copied().unwrap_or(Severity::Warn)
+    }
+}